@@ -0,0 +1,312 @@
+use std::fmt::Write;
+
+use sorm::schema::Column;
+
+/// Returns `true` if `table` matches one of `tables` (or `tables` is empty) and none of `excludes`.
+pub fn filter(table: &str, tables: &[String], excludes: &[String]) -> bool {
+    (tables.is_empty() || tables.iter().any(|v| matches(v, table)))
+        && !excludes.iter().any(|v| matches(v, table))
+}
+
+/// Matches `name` against `pattern`, where `*` matches any sequence of characters.
+fn matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Renders the `#[sorm]` structs for the given tables.
+///
+/// The columns that cannot be generated, whose type has no mapping or whose name is not a valid
+/// field name, are written as `// TODO` comments in their struct and returned as warnings.
+pub fn render(tables: &[(String, Vec<Column>)]) -> (String, Vec<String>) {
+    let mut s = String::with_capacity(256 * tables.len());
+    let mut warnings = Vec::new();
+    s.push_str("// Generated by sorm-cli.\n\nuse sorm::sorm;\n");
+    for (table, columns) in tables {
+        s.push('\n');
+        render_struct(&mut s, &mut warnings, table, columns);
+    }
+    (s, warnings)
+}
+
+fn render_struct(s: &mut String, warnings: &mut Vec<String>, table: &str, columns: &[Column]) {
+    let primary_keys = columns.iter().filter(|v| v.primary_key).count();
+    if primary_keys > 1 {
+        s.push_str("// Composite primary keys are not supported.\n");
+    }
+    writeln!(s, "#[sorm(table = {:?})]", table).unwrap();
+    writeln!(s, "pub struct {} {{", struct_name(table)).unwrap();
+    for column in columns {
+        let Some(field) = field_name(&column.name) else {
+            writeln!(
+                s,
+                "    // TODO: the column `{}` is not a valid field name.",
+                column.name
+            )
+            .unwrap();
+            warnings.push(format!("{}.{}: not a valid field name", table, column.name));
+            continue;
+        };
+        let Some(ty) = rust_type(&column.type_name) else {
+            writeln!(
+                s,
+                "    // TODO: no mapping for the type {}.\n    // {}: {},",
+                column.type_name, field, column.type_name
+            )
+            .unwrap();
+            warnings.push(format!(
+                "{}.{}: no mapping for the type {}",
+                table, column.name, column.type_name
+            ));
+            continue;
+        };
+        if column.primary_key && primary_keys == 1 {
+            match column.increment && is_int(ty) {
+                true => s.push_str("    #[sorm(primary_key(increment))]\n"),
+                false => s.push_str("    #[sorm(primary_key)]\n"),
+            }
+        }
+        match column.nullable {
+            true => writeln!(s, "    {}: Option<{}>,", field, ty).unwrap(),
+            false => writeln!(s, "    {}: {},", field, ty).unwrap(),
+        }
+    }
+    s.push_str("}\n");
+}
+
+/// Converts a snake case table name to a camel case struct name.
+fn struct_name(table: &str) -> String {
+    let mut name = String::with_capacity(table.len());
+    for part in table.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(c) = chars.next() {
+            name.push(c.to_ascii_uppercase());
+            name.extend(chars);
+        }
+    }
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => name,
+        _ => format!("Table{}", name),
+    }
+}
+
+/// Returns the field name of a column, a raw identifier like `r#type` for the keywords, or
+/// `None` if the column name cannot be a field name.
+fn field_name(name: &str) -> Option<String> {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern",
+        "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use",
+        "where", "while", "abstract", "become", "box", "do", "final", "gen", "macro", "override",
+        "priv", "try", "typeof", "unsized", "virtual", "yield",
+    ];
+    // The keywords that cannot be raw identifiers.
+    const RESERVED: &[&str] = &["crate", "self", "Self", "super", "_"];
+
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c == '_' || c.is_ascii_alphabetic() => (),
+        _ => return None,
+    }
+    if RESERVED.contains(&name)
+        || name.starts_with("__sorm")
+        || !chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+    {
+        return None;
+    }
+    match KEYWORDS.contains(&name) {
+        true => Some(format!("r#{}", name)),
+        false => Some(name.to_string()),
+    }
+}
+
+fn is_int(ty: &str) -> bool {
+    matches!(ty, "i32" | "i64" | "u32" | "u64")
+}
+
+#[cfg(feature = "sqlite")]
+fn rust_type(type_name: &str) -> Option<&'static str> {
+    // https://www.sqlite.org/datatype3.html#determination_of_column_affinity
+    let type_name = type_name.to_ascii_uppercase();
+    if type_name.contains("BOOL") {
+        Some("bool")
+    } else if type_name.contains("INT") {
+        Some("i64")
+    } else if type_name.contains("CHAR") || type_name.contains("CLOB") || type_name.contains("TEXT")
+    {
+        Some("String")
+    } else if type_name.contains("BLOB") || type_name.is_empty() {
+        Some("Vec<u8>")
+    } else if type_name.contains("REAL") || type_name.contains("FLOA") || type_name.contains("DOUB")
+    {
+        Some("f64")
+    } else {
+        None
+    }
+}
+
+#[cfg(feature = "mysql")]
+fn rust_type(type_name: &str) -> Option<&'static str> {
+    let type_name = type_name.to_ascii_lowercase();
+    if type_name.starts_with("tinyint(1)") {
+        return Some("bool");
+    }
+    let unsigned = type_name.contains("unsigned");
+    let base = type_name.split(['(', ' ']).next().unwrap_or_default();
+    let ty = match (base, unsigned) {
+        ("bool" | "boolean", _) => "bool",
+        ("tinyint", false) => "i8",
+        ("tinyint", true) => "u8",
+        ("smallint", false) => "i16",
+        ("smallint", true) => "u16",
+        ("int" | "integer" | "mediumint", false) => "i32",
+        ("int" | "integer" | "mediumint", true) => "u32",
+        ("bigint", false) => "i64",
+        ("bigint", true) => "u64",
+        ("float", _) => "f32",
+        ("double" | "real", _) => "f64",
+        ("char" | "varchar" | "tinytext" | "text" | "mediumtext" | "longtext" | "enum", _) => {
+            "String"
+        }
+        ("binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob", _) => "Vec<u8>",
+        _ => return None,
+    };
+    Some(ty)
+}
+
+#[cfg(feature = "postgres")]
+fn rust_type(type_name: &str) -> Option<&'static str> {
+    let ty = match type_name.to_ascii_lowercase().as_str() {
+        "bool" => "bool",
+        "char" => "i8",
+        "int2" => "i16",
+        "int4" => "i32",
+        "int8" => "i64",
+        "float4" => "f32",
+        "float8" => "f64",
+        "text" | "varchar" | "bpchar" | "name" | "citext" => "String",
+        "bytea" => "Vec<u8>",
+        _ => return None,
+    };
+    Some(ty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "sqlite")]
+    fn column(name: &str, type_name: &str, nullable: bool, primary_key: bool) -> Column {
+        Column {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+            nullable,
+            primary_key,
+            increment: primary_key,
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let tables = ["user*".to_string()];
+        let excludes = ["*_log".to_string()];
+        assert!(filter("users", &tables, &excludes));
+        assert!(!filter("posts", &tables, &excludes));
+        assert!(!filter("user_log", &tables, &excludes));
+        assert!(filter("posts", &[], &excludes));
+    }
+
+    #[test]
+    fn test_struct_name() {
+        assert_eq!(struct_name("users"), "Users");
+        assert_eq!(struct_name("user_profiles"), "UserProfiles");
+        assert_eq!(struct_name("2fa"), "Table2fa");
+    }
+
+    #[test]
+    fn test_field_name() {
+        assert_eq!(field_name("name").as_deref(), Some("name"));
+        assert_eq!(field_name("type").as_deref(), Some("r#type"));
+        assert_eq!(field_name("self"), None);
+        assert_eq!(field_name("created at"), None);
+        assert_eq!(field_name("2fa"), None);
+        assert_eq!(field_name("__sorm_set"), None);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_render() {
+        let (source, warnings) = render(&[(
+            "user_profiles".to_string(),
+            vec![
+                column("id", "INTEGER", false, true),
+                column("name", "VARCHAR(64)", false, false),
+                column("bio", "TEXT", true, false),
+                column("type", "TEXT", false, false),
+            ],
+        )]);
+        assert!(warnings.is_empty());
+        assert_eq!(
+            source,
+            r#"// Generated by sorm-cli.
+
+use sorm::sorm;
+
+#[sorm(table = "user_profiles")]
+pub struct UserProfiles {
+    #[sorm(primary_key(increment))]
+    id: i64,
+    name: String,
+    bio: Option<String>,
+    r#type: String,
+}
+"#
+        );
+
+        let (source, warnings) = render(&[(
+            "events".to_string(),
+            vec![
+                column("id", "INTEGER", false, true),
+                column("at", "DATETIME", false, false),
+                column("self", "TEXT", false, false),
+            ],
+        )]);
+        assert_eq!(
+            source,
+            r#"// Generated by sorm-cli.
+
+use sorm::sorm;
+
+#[sorm(table = "events")]
+pub struct Events {
+    #[sorm(primary_key(increment))]
+    id: i64,
+    // TODO: no mapping for the type DATETIME.
+    // at: DATETIME,
+    // TODO: the column `self` is not a valid field name.
+}
+"#
+        );
+        assert_eq!(
+            warnings,
+            [
+                "events.at: no mapping for the type DATETIME",
+                "events.self: not a valid field name"
+            ]
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use sorm::schema;
+use sorm::sqlx::Pool;
+
+mod generate;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generates `#[sorm]` structs from the tables of an existing database.
+    Generate {
+        /// The database url, e.g. `sqlite://app.db` or `postgres://localhost/app`.
+        #[arg(long, env = "DATABASE_URL")]
+        url: String,
+
+        /// Only generates the given tables. `*` matches any sequence of characters.
+        #[arg(long = "table", value_name = "PATTERN")]
+        tables: Vec<String>,
+
+        /// Skips the given tables. `*` matches any sequence of characters.
+        #[arg(long = "exclude", value_name = "PATTERN")]
+        excludes: Vec<String>,
+
+        /// Writes the generated source to the file instead of stdout.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    match Cli::parse().command {
+        Command::Generate {
+            url,
+            tables,
+            excludes,
+            output,
+        } => {
+            let db = Pool::<sorm::Database>::connect(&url).await?;
+            let mut list = Vec::new();
+            for table in schema::tables(&db).await? {
+                if !generate::filter(&table, &tables, &excludes) {
+                    continue;
+                }
+                let columns = schema::columns(&db, &table).await?;
+                list.push((table, columns));
+            }
+
+            let (source, warnings) = generate::render(&list);
+            for warning in warnings {
+                eprintln!(
+                    "warning: {}, see the TODO comments in the generated source",
+                    warning
+                );
+            }
+            match output {
+                Some(path) => fs::write(path, source)?,
+                None => print!("{}", source),
+            }
+        }
+    }
+    Ok(())
+}
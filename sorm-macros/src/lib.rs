@@ -3,6 +3,7 @@ use std::str::FromStr;
 
 use proc_macro2::{Ident, Span};
 use quote::{format_ident, quote, quote_spanned};
+use syn::ext::IdentExt;
 use syn::spanned::Spanned;
 use syn::{
     parse2, parse_macro_input, parse_quote, parse_str, Error, Expr, ItemStruct, Type, Visibility,
@@ -51,6 +52,7 @@ fn expand(attr: ContainerAttr, item: &mut ItemStruct) -> syn::Result<proc_macro2
     item.vis = parse_quote!(pub);
     let ident = &item.ident;
     let module = format_ident!("__sorm_{}", ident.to_string().to_ascii_lowercase());
+    let columns = columns_ident(ident);
     Ok(quote! {
         mod #module {
             use super::*;
@@ -62,9 +64,16 @@ fn expand(attr: ContainerAttr, item: &mut ItemStruct) -> syn::Result<proc_macro2
             #impl_deserialize
         }
         #vis use #module::#ident;
+        #[allow(unused_imports)]
+        #vis use #module::#columns;
     })
 }
 
+/// The name of the typed columns of a model, prefixed to stay out of the way of the user types.
+fn columns_ident(ident: &Ident) -> Ident {
+    format_ident!("__Sorm{}Columns", ident)
+}
+
 fn impl_self(item: &ItemStruct, fields: &Fields) -> proc_macro2::TokenStream {
     let fields_name = fields.names();
     let fields_ident = fields.idents();
@@ -83,11 +92,25 @@ fn impl_self(item: &ItemStruct, fields: &Fields) -> proc_macro2::TokenStream {
         .collect::<Vec<_>>();
     let seq = fields.seq();
     let ident = &item.ident;
+    let columns = columns_ident(ident);
     let new = gen_new(&fields_ident, &fields_type);
     quote! {
+        /// The typed columns of the model, returned by its `col` function.
+        pub struct #columns {
+            #(pub #fields_ident: sorm::expr::Column<#fields_type>,)*
+        }
+
         impl #ident {
             #(pub const #fields_ident_upper: &'static str = #fields_name;)*
 
+            /// Returns the typed columns for building predicates.
+            #[inline]
+            pub const fn col() -> #columns {
+                #columns {
+                    #(#fields_ident: sorm::expr::Column::new(#fields_name),)*
+                }
+            }
+
             #new
 
             #(
@@ -338,6 +361,7 @@ fn impl_model(
     let table = table.unwrap_or_else(|| camel_to_snake(&ident.to_string()));
 
     let primary_key = gen_primary_key(fields);
+    let describe = gen_describe(fields);
     let fill_create_default = gen_fill_create_default(fields);
     let fill_update_default = gen_fill_update_default(fields);
 
@@ -356,6 +380,8 @@ fn impl_model(
 
             #primary_key
 
+            #describe
+
             #[inline]
             fn flush(&mut self) {
                 self.__sorm_update = 0;
@@ -406,6 +432,21 @@ fn impl_model(
                 fields
             }
 
+            fn load_row(&mut self, row: &sorm::Row) -> sorm::sqlx::Result<()> {
+                use sorm::sqlx::Row;
+                #(
+                    match row.try_get(#fields_name) {
+                        Ok(v) => {
+                            self.#fields_ident = v;
+                            self.__sorm_set |= 1 << #seq;
+                        }
+                        Err(sorm::sqlx::Error::ColumnNotFound(_)) => (),
+                        Err(err) => return Err(err),
+                    }
+                )*
+                Ok(())
+            }
+
             #fill_create_default
 
             #fill_update_default
@@ -418,7 +459,7 @@ fn gen_primary_key(fields: &Fields) -> proc_macro2::TokenStream {
         Some((field, increment)) => {
             let ty = &field.inner.ty;
             let ident = field.inner.ident.as_ref().unwrap();
-            let name = ident.to_string();
+            let name = ident.unraw().to_string();
 
             let setter = format_ident!("set_{}", name);
             let increment = if increment {
@@ -474,6 +515,46 @@ fn gen_primary_key(fields: &Fields) -> proc_macro2::TokenStream {
     }
 }
 
+fn gen_describe(fields: &Fields) -> proc_macro2::TokenStream {
+    let fields_name = fields.names();
+    let fields_type = fields.types();
+    let nullable = fields_type.iter().map(|v| is_option(v)).collect::<Vec<_>>();
+    let primary_key = fields.primary_key();
+    let mut is_primary_key = Vec::with_capacity(fields.0.len());
+    let mut increment = Vec::with_capacity(fields.0.len());
+    for field in &fields.0 {
+        match primary_key {
+            Some((v, inc)) if v.seq == field.seq => {
+                is_primary_key.push(true);
+                increment.push(inc);
+            }
+            _ => {
+                is_primary_key.push(false);
+                increment.push(false);
+            }
+        }
+    }
+
+    quote! {
+        fn describe() -> Vec<sorm::schema::Column> {
+            use sorm::sqlx::TypeInfo;
+            vec![
+                #(
+                    sorm::schema::Column {
+                        name: #fields_name.to_string(),
+                        type_name: <#fields_type as sorm::sqlx::Type<sorm::Database>>::type_info()
+                            .name()
+                            .to_string(),
+                        nullable: #nullable,
+                        primary_key: #is_primary_key,
+                        increment: #increment,
+                    },
+                )*
+            ]
+        }
+    }
+}
+
 fn gen_fill_create_default(fields: &Fields) -> proc_macro2::TokenStream {
     let mut gen = Vec::new();
     for field in &fields.0 {
@@ -599,7 +680,8 @@ impl<'a> Fields<'a> {
     fn names(&self) -> Vec<String> {
         self.0
             .iter()
-            .map(|v| v.inner.ident.as_ref().unwrap().to_string())
+            // A raw identifier like `r#type` is the column `type`.
+            .map(|v| v.inner.ident.as_ref().unwrap().unraw().to_string())
             .collect()
     }
 
@@ -660,6 +742,17 @@ fn modify_item(item: &mut ItemStruct, attr_index: Vec<(usize, usize)>) -> syn::R
     }
 }
 
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(v) if v.qself.is_none() => v
+            .path
+            .segments
+            .last()
+            .map_or(false, |v| v.ident == "Option"),
+        _ => false,
+    }
+}
+
 fn size_type(number: usize) -> &'static str {
     match number {
         0..=8 => "u8",
@@ -0,0 +1,218 @@
+use sorm::filter::{Filter, Op};
+use sorm::model::Model;
+use sorm::{sorm, Error};
+
+#[sorm(table = "users")]
+struct User {
+    #[sorm(primary_key(increment))]
+    id: i64,
+    name: String,
+    age: i32,
+    deleted_at: Option<i64>,
+}
+
+fn user_filter() -> sorm::Result<Filter<User>> {
+    Ok(Filter::new()
+        .allow("id", &[Op::Eq, Op::In])?
+        .allow("name", &[Op::Eq, Op::Ne, Op::Like])?
+        .allow("age", &[Op::Lt, Op::Lte, Op::Gt, Op::Gte])?
+        .allow("deleted_at", &[Op::IsNull])?
+        .sortable(&["id", "age"])?
+        .ignore(&["page"]))
+}
+
+#[test]
+fn test_parse() {
+    let filter = user_filter().unwrap();
+    let criteria = filter
+        .parse([
+            ("name__like", "foo%"),
+            ("age__gte", "18"),
+            ("age__lt", "30"),
+            ("id__in", "1,2,3"),
+            ("deleted_at__isnull", "true"),
+            ("sort", "-age, id"),
+            ("page", "2"),
+        ])
+        .unwrap();
+    assert!(!criteria.is_empty());
+    let mut query = User::query();
+    criteria.apply(&mut query);
+    let sql = query.to_sql();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        sql.sql,
+        "SELECT \"id\",\"name\",\"age\",\"deleted_at\" FROM \"users\" WHERE (\"name\" LIKE $1) \
+        AND (\"age\">=CAST($2 AS INT4)) AND (\"age\"<CAST($3 AS INT4)) \
+        AND (\"id\" IN (CAST($4 AS INT8),CAST($5 AS INT8),CAST($6 AS INT8))) \
+        AND (\"deleted_at\" IS NULL) ORDER BY \"age\" DESC,\"id\""
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        sql.sql,
+        "SELECT `id`,`name`,`age`,`deleted_at` FROM `users` WHERE (`name` LIKE ?) \
+        AND (`age`>=?) AND (`age`<?) AND (`id` IN (?,?,?)) AND (`deleted_at` IS NULL) \
+        ORDER BY `age` DESC,`id`"
+    );
+    assert_eq!(
+        sql.params,
+        ["\"foo%\"", "\"18\"", "\"30\"", "\"1\"", "\"2\"", "\"3\""]
+    );
+
+    let criteria = filter
+        .parse([("name", "a'b OR 1=1"), ("name__ne", "--")])
+        .unwrap();
+    let mut query = User::query();
+    criteria.apply(&mut query);
+    let sql = query.to_sql();
+    #[cfg(feature = "postgres")]
+    assert!(sql.sql.ends_with(
+        "WHERE (\"name\"=CAST($1 AS TEXT)) AND (\"name\"<>CAST($2 AS TEXT))"
+    ));
+    #[cfg(not(feature = "postgres"))]
+    assert!(sql.sql.ends_with("WHERE (`name`=?) AND (`name`<>?)"));
+    assert_eq!(sql.params, ["\"a'b OR 1=1\"", "\"--\""]);
+
+    assert!(filter.parse(Vec::<(String, String)>::new()).unwrap().is_empty());
+}
+
+#[test]
+fn test_parse_error() {
+    let filter = user_filter().unwrap();
+    let error = |params: &[(&str, &str)]| match filter.parse(params.iter().copied()) {
+        Err(Error::InvalidFilter(message)) => message,
+        Err(err) => panic!("unexpected error {:?}", err),
+        Ok(_) => panic!("expected an error"),
+    };
+    assert_eq!(error(&[("password", "foo")]), "unknown field `password`");
+    assert_eq!(error(&[("password__eq", "foo")]), "unknown field `password__eq`");
+    assert_eq!(
+        error(&[("name__regex", ".*")]),
+        "unknown operator `regex` of `name__regex`"
+    );
+    assert_eq!(
+        error(&[("age", "18")]),
+        "operator `eq` is not allowed on `age`"
+    );
+    assert_eq!(
+        error(&[("deleted_at__isnull", "1")]),
+        "`deleted_at__isnull` expects true or false"
+    );
+    assert_eq!(
+        error(&[("age__gte", "abc")]),
+        "`age__gte` expects an integer, got `abc`"
+    );
+    assert_eq!(
+        error(&[("age__lt", "1.5")]),
+        "`age__lt` expects an integer, got `1.5`"
+    );
+    assert_eq!(
+        error(&[("id__in", "1,x")]),
+        "`id__in` expects an integer, got `x`"
+    );
+    assert_eq!(error(&[("sort", "-name")]), "unknown sort field `name`");
+    assert_eq!(
+        error(&[("sort", "id:desc")]),
+        "unknown sort field `id:desc`"
+    );
+    assert_eq!(
+        error(&[("sort", "id:nulls_last")]),
+        "the position of the NULL values of `id` cannot be set"
+    );
+    assert_eq!(
+        Error::InvalidFilter("unknown field `password`".to_string()).to_string(),
+        "invalid filter: unknown field `password`"
+    );
+}
+
+#[sorm(table = "products")]
+struct Product {
+    #[sorm(primary_key(increment))]
+    id: i64,
+    price: f64,
+    active: bool,
+}
+
+#[test]
+fn test_parse_types() {
+    let filter = Filter::<Product>::new()
+        .allow("price", &[Op::Gte])
+        .unwrap()
+        .allow("active", &[Op::Eq])
+        .unwrap();
+    let criteria = filter
+        .parse([("price__gte", "9.5"), ("active", "true")])
+        .unwrap();
+    let mut query = Product::query();
+    criteria.apply(&mut query);
+    assert_eq!(query.to_sql().params, ["\"9.5\"", "\"1\""]);
+
+    let error = |params: &[(&str, &str)]| match filter.parse(params.iter().copied()) {
+        Err(Error::InvalidFilter(message)) => message,
+        Err(err) => panic!("unexpected error {:?}", err),
+        Ok(_) => panic!("expected an error"),
+    };
+    assert_eq!(
+        error(&[("price__gte", "cheap")]),
+        "`price__gte` expects a number, got `cheap`"
+    );
+    assert_eq!(
+        error(&[("price__gte", "NaN")]),
+        "`price__gte` expects a number, got `NaN`"
+    );
+    assert_eq!(
+        error(&[("active", "yes")]),
+        "`active` expects a boolean, got `yes`"
+    );
+}
+
+#[test]
+fn test_allow_error() {
+    assert!(matches!(
+        Filter::<User>::new().allow("password", &[Op::Eq]),
+        Err(Error::UnknownColumn(column)) if column == "password"
+    ));
+    assert!(matches!(
+        Filter::<User>::new().sortable(&["id", "password"]),
+        Err(Error::UnknownColumn(column)) if column == "password"
+    ));
+    match Filter::<User>::new().allow("age", &[Op::Eq, Op::Like]) {
+        Err(Error::InvalidFilter(message)) => assert_eq!(
+            message,
+            "operator `like` is not allowed on the non-text column `age`"
+        ),
+        _ => panic!("expected an error"),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[sqlx::test]
+async fn test_apply() {
+    use sqlx::{Connection, Executor, SqliteConnection};
+
+    let _guard = crate::query::LOCK.lock().unwrap();
+    let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+    conn.execute(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, age INTEGER, deleted_at INTEGER);
+        INSERT INTO users (id, name, age, deleted_at) VALUES
+            (1,'foo',20,NULL),(2,'foobar',9,NULL),(3,'bar',40,NULL),(4,'foo',30,1),(5,'food',25,NULL)",
+    )
+    .await
+    .unwrap();
+
+    let criteria = user_filter()
+        .unwrap()
+        .parse([
+            ("name__like", "foo%"),
+            ("age__gte", "18"),
+            ("deleted_at__isnull", "true"),
+            ("sort", "-age"),
+        ])
+        .unwrap();
+    let mut query = User::query();
+    criteria.apply(&mut query);
+    let users = query.get(&mut conn).await.unwrap();
+    let ids: Vec<_> = users.iter().map(|v| *v.id().unwrap()).collect();
+    assert_eq!(ids, [5, 1]);
+    sorm::query::test::QUERY.take();
+}
@@ -1,5 +1,14 @@
+#[cfg(test)]
+mod filter;
+
 #[cfg(test)]
 mod model;
 
 #[cfg(test)]
 mod query;
+
+#[cfg(test)]
+mod schema;
+
+#[cfg(test)]
+mod transaction;
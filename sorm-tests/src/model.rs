@@ -32,6 +32,23 @@ fn test_table() {
     }
 }
 
+#[test]
+fn test_raw_ident() {
+    #[sorm(table = "events")]
+    struct Event {
+        #[sorm(primary_key)]
+        r#type: String,
+        r#ref: i64,
+    }
+    assert_eq!(Event::COLUMNS, ["type", "ref"]);
+    assert_eq!(Event::PRIMARY_KEY, "type");
+
+    let mut event = Event::new();
+    event.set_type("click".to_string());
+    assert_eq!(event.r#type().unwrap(), "click");
+    assert_eq!(Event::col().r#ref.name(), "ref");
+}
+
 #[test]
 fn test_primary_key() {
     {
@@ -146,3 +163,354 @@ fn test_deserialize() {
     assert_eq!(filled[0].0, "name");
     assert!(param_equal(filled[0].1, &"foo"));
 }
+
+#[cfg(feature = "sqlite")]
+#[sqlx::test]
+async fn test_increment() {
+    use sqlx::{Connection, Executor, SqliteConnection};
+
+    #[sorm(table = "posts")]
+    struct Post {
+        #[sorm(primary_key(increment))]
+        id: i64,
+        title: String,
+        views: i64,
+        #[sorm(update_time = "100")]
+        updated_at: i64,
+    }
+
+    let _guard = crate::query::LOCK.lock().unwrap();
+
+    let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+    conn.execute(
+        "CREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT, views INTEGER, updated_at INTEGER);
+        INSERT INTO posts (id, title, views, updated_at) VALUES (1,'foo',10,0)",
+    )
+    .await
+    .unwrap();
+
+    let mut post = Post::find(&mut conn, &1).await.unwrap();
+    // Another client increments the counter meanwhile.
+    conn.execute("UPDATE posts SET views=views+5").await.unwrap();
+    post.set_title("bar".to_string());
+    post.increment(&mut conn, "views", 2).await.unwrap();
+    assert_eq!(post.views().unwrap(), &17);
+    assert_eq!(post.updated_at().unwrap(), &100);
+    post.decrement(&mut conn, "views", 7).await.unwrap();
+    assert_eq!(post.views().unwrap(), &10);
+    // The other changed fields are left for the next update.
+    assert!(post.is_changed());
+
+    let mut found = Post::find(&mut conn, &1).await.unwrap();
+    assert_eq!(found.title().unwrap(), "foo");
+    assert_eq!(found.views().unwrap(), &10);
+    assert_eq!(found.updated_at().unwrap(), &100);
+    post.update(&mut conn).await.unwrap();
+    found = Post::find(&mut conn, &1).await.unwrap();
+    assert_eq!(found.title().unwrap(), "bar");
+    assert_eq!(found.views().unwrap(), &10);
+
+    let mut post = Post::new();
+    post.set_id(2);
+    assert!(matches!(
+        post.increment(&mut conn, "views", 1).await,
+        Err(sorm::Error::Sqlx(sqlx::Error::RowNotFound))
+    ));
+
+    let mut post = Post::find(&mut conn, &1).await.unwrap();
+    assert!(matches!(
+        post.increment(&mut conn, "likes", 1).await,
+        Err(sorm::Error::UnknownColumn(column)) if column == "likes"
+    ));
+    assert!(!post.is_changed());
+}
+
+#[cfg(feature = "sqlite")]
+#[sqlx::test]
+async fn test_bulk() {
+    use sqlx::{Connection, Executor, SqliteConnection};
+
+    #[sorm(table = "posts")]
+    struct Post {
+        #[sorm(primary_key)]
+        id: i64,
+        title: String,
+        views: i64,
+        #[sorm(update_time = "100")]
+        updated_at: i64,
+    }
+
+    let _guard = crate::query::LOCK.lock().unwrap();
+
+    let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+    conn.execute(
+        "CREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT, views INTEGER, updated_at INTEGER);
+        WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n+1 FROM seq WHERE n<40000)
+        INSERT INTO posts SELECT n, 'post ' || n, 0, 0 FROM seq",
+    )
+    .await
+    .unwrap();
+
+    assert!(Post::find_many(&mut conn, &[] as &[i64])
+        .await
+        .unwrap()
+        .is_empty());
+    let mut posts = Post::find_many(&mut conn, &[3, 1, 99999]).await.unwrap();
+    posts.sort_by_key(|v| *v.id().unwrap());
+    let ids: Vec<_> = posts.iter().map(|v| *v.id().unwrap()).collect();
+    assert_eq!(ids, [1, 3]);
+
+    // More keys than the parameters of a statement.
+    let ids: Vec<i64> = (1..=35000).collect();
+    let mut posts = Post::find_many(&mut conn, &ids).await.unwrap();
+    assert_eq!(posts.len(), 35000);
+
+    posts.sort_by_key(|v| *v.id().unwrap());
+    for post in &mut posts[..15000] {
+        let views = *post.id().unwrap() * 2;
+        post.set_views(views);
+    }
+    posts[0].set_title("first".to_string());
+    posts[1].set_title("second".to_string());
+    posts[20000].set_title("other".to_string());
+    assert_eq!(Post::update_many(&mut conn, &mut posts).await.unwrap(), 15001);
+    assert!(posts.iter().all(|v| !v.is_changed()));
+    assert_eq!(posts[0].updated_at().unwrap(), &100);
+
+    let mut posts = Post::find_many(&mut conn, &[1, 2, 15000, 15001, 20001])
+        .await
+        .unwrap();
+    posts.sort_by_key(|v| *v.id().unwrap());
+    let posts: Vec<_> = posts
+        .iter()
+        .map(|v| {
+            (
+                *v.id().unwrap(),
+                v.title().unwrap().as_str(),
+                *v.views().unwrap(),
+                *v.updated_at().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        posts,
+        [
+            (1, "first", 2, 100),
+            (2, "second", 4, 100),
+            (15000, "post 15000", 30000, 100),
+            (15001, "post 15001", 0, 0),
+            (20001, "other", 0, 100),
+        ]
+    );
+    assert_eq!(Post::update_many(&mut conn, &mut []).await.unwrap(), 0);
+
+    // A changed primary key alone is not updated, and the model stays changed.
+    let mut posts = Post::find_many(&mut conn, &[1, 2]).await.unwrap();
+    posts.sort_by_key(|v| *v.id().unwrap());
+    posts[0].set_id(50000);
+    posts[1].set_views(1);
+    assert_eq!(Post::update_many(&mut conn, &mut posts).await.unwrap(), 1);
+    assert!(posts[0].is_changed());
+    assert!(!posts[1].is_changed());
+
+    // A model without primary key fails the call before any model is filled.
+    let mut posts = vec![Post::find(&mut conn, &3).await.unwrap(), Post::new()];
+    posts[0].set_updated_at(0);
+    posts[1].set_views(1);
+    assert!(Post::update_many(&mut conn, &mut posts).await.is_err());
+    assert_eq!(posts[0].updated_at().unwrap(), &0);
+    assert!(posts[1].updated_at().is_err());
+
+    assert_eq!(Post::destroy_many(&mut conn, &ids).await.unwrap(), 35000);
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM posts")
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(count, 5000);
+}
+
+#[cfg(feature = "sqlite")]
+#[sqlx::test]
+async fn test_first_or_create() {
+    use sorm::model::Fill;
+    use sqlx::{Connection, Executor, SqliteConnection};
+
+    #[sorm(table = "users")]
+    struct User {
+        #[sorm(primary_key(increment))]
+        id: i64,
+        email: String,
+        name: String,
+        #[sorm(default = "1")]
+        status: i64,
+        #[sorm(create_time = "100")]
+        created_at: i64,
+        #[sorm(update_time = "200")]
+        updated_at: i64,
+    }
+
+    struct Email(&'static str);
+
+    impl Fill<User> for Email {
+        fn fill(self, model: &mut User) {
+            model.set_email(self.0.to_owned());
+        }
+    }
+
+    struct Name(&'static str);
+
+    impl Fill<User> for Name {
+        fn fill(self, model: &mut User) {
+            model.set_name(self.0.to_owned());
+        }
+    }
+
+    let _guard = crate::query::LOCK.lock().unwrap();
+
+    let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+    conn.execute(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT UNIQUE, name TEXT, status INTEGER, created_at INTEGER, updated_at INTEGER);
+        INSERT INTO users VALUES (1,'foo@example.com','foo',2,0,0)",
+    )
+    .await
+    .unwrap();
+
+    let user = User::first_or_new(&mut conn, Email("foo@example.com"), Name("bar"))
+        .await
+        .unwrap();
+    assert_eq!(user.id().unwrap(), &1);
+    assert_eq!(user.name().unwrap(), "foo");
+    let user = User::first_or_new(&mut conn, Email("bar@example.com"), Name("bar"))
+        .await
+        .unwrap();
+    assert!(user.id().is_err());
+    assert_eq!(user.email().unwrap(), "bar@example.com");
+    assert_eq!(user.name().unwrap(), "bar");
+
+    let user = User::first_or_create(&mut conn, Email("foo@example.com"), Name("bar"))
+        .await
+        .unwrap();
+    assert_eq!(user.id().unwrap(), &1);
+    assert_eq!(user.name().unwrap(), "foo");
+    let user = User::first_or_create(&mut conn, Email("bar@example.com"), Name("bar"))
+        .await
+        .unwrap();
+    assert_eq!(user.id().unwrap(), &2);
+    assert_eq!(user.name().unwrap(), "bar");
+    assert_eq!(user.status().unwrap(), &1);
+    assert_eq!(user.created_at().unwrap(), &100);
+    assert!(!user.is_changed());
+
+    let user = User::update_or_create(&mut conn, Email("foo@example.com"), Name("baz"))
+        .await
+        .unwrap();
+    assert_eq!(user.id().unwrap(), &1);
+    assert_eq!(user.name().unwrap(), "baz");
+    assert_eq!(user.status().unwrap(), &2);
+    assert_eq!(user.created_at().unwrap(), &0);
+    assert_eq!(user.updated_at().unwrap(), &200);
+    let user = User::update_or_create(&mut conn, Email("baz@example.com"), Name("baz"))
+        .await
+        .unwrap();
+    assert_eq!(user.id().unwrap(), &3);
+    assert_eq!(user.created_at().unwrap(), &100);
+
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users")
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(count, 3);
+
+    // A `None` attribute matches `NULL`, instead of inserting a duplicate.
+    #[sorm(table = "members")]
+    struct Member {
+        #[sorm(primary_key(increment))]
+        id: i64,
+        email: String,
+        team: Option<String>,
+    }
+
+    struct Membership(&'static str, Option<&'static str>);
+
+    impl Fill<Member> for Membership {
+        fn fill(self, model: &mut Member) {
+            model.set_email(self.0.to_owned());
+            model.set_team(self.1.map(str::to_owned));
+        }
+    }
+
+    struct NoDefaults;
+
+    impl Fill<Member> for NoDefaults {
+        fn fill(self, _model: &mut Member) {}
+    }
+
+    conn.execute(
+        "CREATE TABLE members (id INTEGER PRIMARY KEY, email TEXT, team TEXT, UNIQUE (email, team))",
+    )
+    .await
+    .unwrap();
+    let member = Member::first_or_create(&mut conn, Membership("foo@example.com", None), NoDefaults)
+        .await
+        .unwrap();
+    assert_eq!(member.id().unwrap(), &1);
+    let member = Member::first_or_create(&mut conn, Membership("foo@example.com", None), NoDefaults)
+        .await
+        .unwrap();
+    assert_eq!(member.id().unwrap(), &1);
+    assert_eq!(member.team().unwrap(), &None);
+    let member = Member::first_or_new(&mut conn, Membership("foo@example.com", None), NoDefaults)
+        .await
+        .unwrap();
+    assert_eq!(member.id().unwrap(), &1);
+    let member = Member::first_or_create(&mut conn, Membership("foo@example.com", Some("a")), NoDefaults)
+        .await
+        .unwrap();
+    assert_eq!(member.id().unwrap(), &2);
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM members")
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+
+    // The attributes need no unique index.
+    conn.execute("DROP TABLE members; CREATE TABLE members (id INTEGER PRIMARY KEY, email TEXT, team TEXT)")
+        .await
+        .unwrap();
+    let member = Member::first_or_create(&mut conn, Membership("foo@example.com", None), NoDefaults)
+        .await
+        .unwrap();
+    assert_eq!(member.id().unwrap(), &1);
+    let member = Member::first_or_create(&mut conn, Membership("foo@example.com", None), NoDefaults)
+        .await
+        .unwrap();
+    assert_eq!(member.id().unwrap(), &1);
+    let member = Member::update_or_create(
+        &mut conn,
+        Membership("bar@example.com", Some("a")),
+        NoDefaults,
+    )
+    .await
+    .unwrap();
+    assert_eq!(member.id().unwrap(), &2);
+
+    // A conflict on another unique index is an error.
+    conn.execute("CREATE UNIQUE INDEX members_email ON members (email)")
+        .await
+        .unwrap();
+    assert!(Member::first_or_create(&mut conn, Membership("foo@example.com", Some("b")), NoDefaults)
+        .await
+        .is_err());
+
+    // Looking up without attributes would match any row.
+    struct Nothing;
+
+    impl Fill<User> for Nothing {
+        fn fill(self, _model: &mut User) {}
+    }
+
+    assert!(matches!(
+        User::first_or_create(&mut conn, Nothing, Name("bar")).await,
+        Err(sorm::Error::NoWhereClause)
+    ));
+}
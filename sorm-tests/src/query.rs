@@ -1,7 +1,7 @@
 use futures_util::stream::empty;
-use sorm::query::{test, Query, Update};
+use sorm::query::{test, ClauseOwned, Query, QueryOwned, Update};
 use sorm::sqlx::{Database, Describe, Either, Error, Execute, Executor};
-use sorm::{clause, Param};
+use sorm::{clause, sorm, Param};
 use sqlx::FromRow;
 use std::io;
 use std::sync::Mutex;
@@ -78,7 +78,7 @@ struct User {
     name: String,
 }
 
-static LOCK: Mutex<()> = Mutex::new(());
+pub(crate) static LOCK: Mutex<()> = Mutex::new(());
 
 #[sqlx::test]
 async fn test_find() {
@@ -132,10 +132,13 @@ async fn test_where() {
     #[cfg(feature = "postgres")]
     assert_eq!(
         query[0].0,
-        "SELECT * FROM \"users\" WHERE id=$1 AND name=$2"
+        "SELECT * FROM \"users\" WHERE (id=$1) AND (name=$2)"
     );
     #[cfg(not(feature = "postgres"))]
-    assert_eq!(query[0].0, "SELECT * FROM `users` WHERE id=? AND name=?");
+    assert_eq!(
+        query[0].0,
+        "SELECT * FROM `users` WHERE (id=?) AND (name=?)"
+    );
     assert_eq!(&query[0].1, params![&1, &"foo"]);
 
     let _ = Query::table("users")
@@ -146,12 +149,60 @@ async fn test_where() {
     let query = test::QUERY.take();
     assert_eq!(query.len(), 1);
     #[cfg(feature = "postgres")]
-    assert_eq!(query[0].0, "SELECT * FROM \"users\" WHERE id=$1 OR name=$2");
+    assert_eq!(
+        query[0].0,
+        "SELECT * FROM \"users\" WHERE (id=$1) OR (name=$2)"
+    );
     #[cfg(not(feature = "postgres"))]
-    assert_eq!(query[0].0, "SELECT * FROM `users` WHERE id=? OR name=?");
+    assert_eq!(query[0].0, "SELECT * FROM `users` WHERE (id=?) OR (name=?)");
     assert_eq!(&query[0].1, params![&1, &"foo"]);
 }
 
+#[sqlx::test]
+async fn test_where_group() {
+    let _guard = LOCK.lock().unwrap();
+
+    let id = 1;
+    let name = "foo";
+    let status = 2;
+    let min = 10;
+    let by_status = clause!("status={status}");
+    let by_amount = clause!("SUM(amount)>{min}");
+    let _ = Query::table("users")
+        .r#where(clause!("id={id}"))
+        .or_where(clause!("name={&name}"))
+        .where_group(|q| {
+            q.r#where(by_status).or_where("status IS NULL");
+        })
+        .where_group(|_| {})
+        .or_where_group(|q| {
+            q.r#where("deleted=1");
+        })
+        .group_by(&["id"])
+        .having("COUNT(0)>1")
+        .having_group(|q| {
+            q.having(by_amount).or_having("SUM(amount) IS NULL");
+        })
+        .get::<User>(DummyDB)
+        .await;
+    let query = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query[0].0,
+        "SELECT * FROM \"users\" WHERE (id=$1) OR (name=$2) AND \
+        ((status=$3) OR (status IS NULL)) OR (deleted=1) GROUP BY \"id\" \
+        HAVING (COUNT(0)>1) AND ((SUM(amount)>$4) OR (SUM(amount) IS NULL))"
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query[0].0,
+        "SELECT * FROM `users` WHERE (id=?) OR (name=?) AND \
+        ((status=?) OR (status IS NULL)) OR (deleted=1) GROUP BY `id` \
+        HAVING (COUNT(0)>1) AND ((SUM(amount)>?) OR (SUM(amount) IS NULL))"
+    );
+    assert_eq!(&query[0].1, params![&1, &"foo", &2, &10]);
+}
+
 #[sqlx::test]
 async fn test_group_by() {
     let _guard = LOCK.lock().unwrap();
@@ -191,12 +242,12 @@ async fn test_group_by() {
     #[cfg(feature = "postgres")]
     assert_eq!(
         query[0].0,
-        "SELECT * FROM \"users\" WHERE status=$1 GROUP BY \"id\" HAVING id=$2 AND name=$3 OR id=2"
+        "SELECT * FROM \"users\" WHERE status=$1 GROUP BY \"id\" HAVING (id=$2) AND (name=$3) OR (id=2)"
     );
     #[cfg(not(feature = "postgres"))]
     assert_eq!(
         query[0].0,
-        "SELECT * FROM `users` WHERE status=? GROUP BY `id` HAVING id=? AND name=? OR id=2"
+        "SELECT * FROM `users` WHERE status=? GROUP BY `id` HAVING (id=?) AND (name=?) OR (id=2)"
     );
     assert_eq!(&query[0].1, params![&3, &1, &"foo"]);
 }
@@ -297,6 +348,30 @@ async fn test_update() {
         "UPDATE `users` SET `name`=?,`updated_at`=NOW() WHERE id=?"
     );
     assert_eq!(&query[0].1, params![&"foo", &1]);
+
+    let (views, stock) = (1, 2);
+    let _ = Query::table("users")
+        .r#where(clause!("id={id}"))
+        .update(
+            DummyDB,
+            &Update::new()
+                .increment("views", &views)
+                .decrement("stock", &stock)
+                .set("name", &name),
+        )
+        .await;
+    let query = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query[0].0,
+        "UPDATE \"users\" SET \"views\"=\"views\"+$1,\"stock\"=\"stock\"-$2,\"name\"=$3 WHERE id=$4"
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query[0].0,
+        "UPDATE `users` SET `views`=`views`+?,`stock`=`stock`-?,`name`=? WHERE id=?"
+    );
+    assert_eq!(&query[0].1, params![&1, &2, &"foo", &1]);
 }
 
 #[sqlx::test]
@@ -316,3 +391,1497 @@ async fn test_delete() {
     assert_eq!(query[0].0, "DELETE FROM `users` WHERE id=?");
     assert_eq!(&query[0].1, params![&1]);
 }
+
+#[sqlx::test]
+async fn test_join() {
+    let _guard = LOCK.lock().unwrap();
+
+    let status = 1;
+    let name = "foo";
+    let min = 100;
+    let _ = Query::table(("users", "u"))
+        .select(&["u.*", "o.amount"])
+        .join(
+            ("orders", "o"),
+            clause!("o.user_id=u.id AND o.status={status}"),
+        )
+        .left_join("profiles", "profiles.user_id=u.id")
+        .r#where(clause!("u.name={&name}"))
+        .group_by(&["u.id"])
+        .having(clause!("SUM(o.amount)>{min}"))
+        .get::<User>(DummyDB)
+        .await;
+    let query = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query[0].0,
+        "SELECT \"u\".*,\"o\".\"amount\" FROM \"users\" AS \"u\" \
+        INNER JOIN \"orders\" AS \"o\" ON o.user_id=u.id AND o.status=$1 \
+        LEFT JOIN \"profiles\" ON profiles.user_id=u.id \
+        WHERE u.name=$2 GROUP BY \"u\".\"id\" HAVING SUM(o.amount)>$3"
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query[0].0,
+        "SELECT `u`.*,`o`.`amount` FROM `users` AS `u` \
+        INNER JOIN `orders` AS `o` ON o.user_id=u.id AND o.status=? \
+        LEFT JOIN `profiles` ON profiles.user_id=u.id \
+        WHERE u.name=? GROUP BY `u`.`id` HAVING SUM(o.amount)>?"
+    );
+    assert_eq!(&query[0].1, params![&1, &"foo", &100]);
+
+    let _ = Query::table("users")
+        .alias("u")
+        .right_join(("orders", "o"), "o.user_id=u.id")
+        .cross_join("tags")
+        .get::<User>(DummyDB)
+        .await;
+    let query = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query[0].0,
+        "SELECT * FROM \"users\" AS \"u\" RIGHT JOIN \"orders\" AS \"o\" ON o.user_id=u.id \
+        CROSS JOIN \"tags\""
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query[0].0,
+        "SELECT * FROM `users` AS `u` RIGHT JOIN `orders` AS `o` ON o.user_id=u.id \
+        CROSS JOIN `tags`"
+    );
+
+    // UPDATE and DELETE leave the alias out and reject joins.
+    let mut query = Query::table("users");
+    query.alias("u").r#where("users.id=1");
+    let _ = query.update(DummyDB, "name='foo'").await;
+    let _ = query.delete(DummyDB).await;
+    let query_log = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    {
+        assert_eq!(
+            query_log[0].0,
+            "UPDATE \"users\" SET name='foo' WHERE users.id=1"
+        );
+        assert_eq!(query_log[1].0, "DELETE FROM \"users\" WHERE users.id=1");
+    }
+    #[cfg(not(feature = "postgres"))]
+    {
+        assert_eq!(query_log[0].0, "UPDATE `users` SET name='foo' WHERE users.id=1");
+        assert_eq!(query_log[1].0, "DELETE FROM `users` WHERE users.id=1");
+    }
+    query.join("orders", "orders.user_id=users.id");
+    assert!(matches!(
+        query.update(DummyDB, "name='foo'").await,
+        Err(sorm::Error::UnsupportedJoin)
+    ));
+    assert!(matches!(
+        query.delete(DummyDB).await,
+        Err(sorm::Error::UnsupportedJoin)
+    ));
+}
+
+#[sqlx::test]
+async fn test_subquery() {
+    let _guard = LOCK.lock().unwrap();
+
+    let status = 1;
+    let name = "foo";
+    let params;
+    let mut orders = Query::table("orders");
+    orders
+        .select(&["user_id"])
+        .r#where(clause!("status={status}", params));
+    let _ = Query::table("users")
+        .r#where(clause!("name={&name}"))
+        .where_in_sub("id", &orders)
+        .get::<User>(DummyDB)
+        .await;
+    let query = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query[0].0,
+        "SELECT * FROM \"users\" WHERE (name=$1) AND \"id\" IN \
+        (SELECT \"user_id\" FROM \"orders\" WHERE status=$2)"
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query[0].0,
+        "SELECT * FROM `users` WHERE (name=?) AND `id` IN \
+        (SELECT `user_id` FROM `orders` WHERE status=?)"
+    );
+    assert_eq!(&query[0].1, params![&"foo", &1]);
+
+    let params;
+    let mut orders = Query::table(("orders", "o"));
+    orders.r#where(clause!("o.user_id=users.id AND o.status={status}", params));
+    let _ = Query::table("users")
+        .where_exists(&orders)
+        .r#where(clause!("name={&name}"))
+        .delete(DummyDB)
+        .await;
+    let query = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query[0].0,
+        "DELETE FROM \"users\" WHERE EXISTS (SELECT * FROM \"orders\" AS \"o\" \
+        WHERE o.user_id=users.id AND o.status=$1) AND (name=$2)"
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query[0].0,
+        "DELETE FROM `users` WHERE EXISTS (SELECT * FROM `orders` AS `o` \
+        WHERE o.user_id=users.id AND o.status=?) AND (name=?)"
+    );
+    assert_eq!(&query[0].1, params![&1, &"foo"]);
+
+    let min = 100;
+    let params;
+    let mut totals = Query::table("orders");
+    totals
+        .select_raw("user_id, SUM(amount) AS total")
+        .r#where(clause!("status={status}", params))
+        .group_by(&["user_id"]);
+    let _ = Query::from_sub(&totals, "t")
+        .r#where(clause!("t.total>{min}"))
+        .get::<User>(DummyDB)
+        .await;
+    let query = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query[0].0,
+        "SELECT * FROM (SELECT user_id, SUM(amount) AS total FROM \"orders\" \
+        WHERE status=$1 GROUP BY \"user_id\") AS \"t\" WHERE t.total>$2"
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query[0].0,
+        "SELECT * FROM (SELECT user_id, SUM(amount) AS total FROM `orders` \
+        WHERE status=? GROUP BY `user_id`) AS `t` WHERE t.total>?"
+    );
+    assert_eq!(&query[0].1, params![&1, &100]);
+}
+
+#[sqlx::test]
+async fn test_union() {
+    let _guard = LOCK.lock().unwrap();
+
+    let status = 1;
+    let params;
+    let mut archive = Query::table("archived_orders");
+    archive
+        .select(&["id", "amount"])
+        .r#where(clause!("status={status}", params));
+    let mut recent = Query::table("orders");
+    recent
+        .select(&["id", "amount"])
+        .order_by_desc("id")
+        .limit(5);
+    let _ = Query::table("orders")
+        .select(&["id", "amount"])
+        .r#where(clause!("status={status}"))
+        .union_all(&archive)
+        .except(&recent)
+        .order_by_desc("amount")
+        .find::<(i64, i64)>(DummyDB)
+        .await;
+    let query = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query[0].0,
+        "SELECT \"id\",\"amount\" FROM \"orders\" WHERE status=$1 \
+        UNION ALL SELECT \"id\",\"amount\" FROM \"archived_orders\" WHERE status=$2 \
+        EXCEPT SELECT * FROM (SELECT \"id\",\"amount\" FROM \"orders\" ORDER BY \"id\" DESC LIMIT 5) AS \"t\" \
+        ORDER BY \"amount\" DESC LIMIT 1"
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query[0].0,
+        "SELECT `id`,`amount` FROM `orders` WHERE status=? \
+        UNION ALL SELECT `id`,`amount` FROM `archived_orders` WHERE status=? \
+        EXCEPT SELECT * FROM (SELECT `id`,`amount` FROM `orders` ORDER BY `id` DESC LIMIT 5) AS `t` \
+        ORDER BY `amount` DESC LIMIT 1"
+    );
+    assert_eq!(&query[0].1, params![&1, &1]);
+}
+
+#[sqlx::test]
+async fn test_predicate() {
+    #[sorm(table = "users")]
+    struct Account {
+        id: i64,
+        name: String,
+        age: i32,
+        deleted_at: Option<i64>,
+    }
+
+    let _guard = LOCK.lock().unwrap();
+
+    let name = "foo%".to_string();
+    let (min, max) = (18, 30);
+    let ids = [1, 2];
+    let col = Account::col();
+    let predicate = col
+        .name
+        .like(&name)
+        .and(col.age.between(&min, &max))
+        .or(col.id.in_list(&ids))
+        .or(col.deleted_at.is_null().not());
+    let empty = col.id.in_list(&[]);
+    let _ = Query::table("users")
+        .r#where(&predicate)
+        .r#where(&empty)
+        .get::<Account>(DummyDB)
+        .await;
+    let query = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query[0].0,
+        "SELECT * FROM \"users\" WHERE ((\"name\" LIKE $1 AND \"age\" BETWEEN $2 AND $3 \
+        OR \"id\" IN ($4,$5) OR NOT (\"deleted_at\" IS NULL))) AND (1=0)"
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query[0].0,
+        "SELECT * FROM `users` WHERE ((`name` LIKE ? AND `age` BETWEEN ? AND ? \
+        OR `id` IN (?,?) OR NOT (`deleted_at` IS NULL))) AND (1=0)"
+    );
+    assert_eq!(&query[0].1, params![&"foo%", &18, &30, &1, &2]);
+
+    // A NULL value is compared with IS NULL.
+    let (none, some) = (None, Some(1));
+    let predicate = col
+        .deleted_at
+        .eq(&none)
+        .and(col.deleted_at.ne(&none))
+        .and(col.deleted_at.eq(&some));
+    let _ = Query::table("users")
+        .r#where(&predicate)
+        .get::<Account>(DummyDB)
+        .await;
+    let query = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query[0].0,
+        "SELECT * FROM \"users\" WHERE \"deleted_at\" IS NULL \
+        AND \"deleted_at\" IS NOT NULL AND \"deleted_at\"=$1"
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query[0].0,
+        "SELECT * FROM `users` WHERE `deleted_at` IS NULL \
+        AND `deleted_at` IS NOT NULL AND `deleted_at`=?"
+    );
+    assert_eq!(&query[0].1, params![&Some(1)]);
+}
+
+#[cfg(feature = "sqlite")]
+#[sqlx::test]
+async fn test_paginate() {
+    use sqlx::{Connection, SqliteConnection};
+
+    let _guard = LOCK.lock().unwrap();
+
+    let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+    conn.execute(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+        INSERT INTO users (name) VALUES ('a'),('b'),('b'),('c'),('d')",
+    )
+    .await
+    .unwrap();
+
+    let page = Query::table("users")
+        .r#where("id>1")
+        .order_by("id")
+        .limit(100)
+        .paginate::<(i64, String)>(&mut conn, 2, 3)
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::to_string(&page).unwrap(),
+        r#"{"items":[[5,"d"]],"total":4,"page":2,"per_page":3,"last_page":2}"#
+    );
+    let query = test::QUERY.take();
+    assert_eq!(query[0].0, "SELECT COUNT(*) FROM `users` WHERE id>1");
+    assert_eq!(
+        query[1].0,
+        "SELECT * FROM `users` WHERE id>1 ORDER BY `id` LIMIT 3 OFFSET 3"
+    );
+
+    let page = Query::table("users")
+        .select(&["name"])
+        .group_by(&["name"])
+        .paginate::<(String,)>(&mut conn, 3, 2)
+        .await
+        .unwrap();
+    assert!(page.items.is_empty());
+    assert_eq!((page.total, page.last_page), (4, 2));
+    let query = test::QUERY.take();
+    assert_eq!(query.len(), 1);
+    assert_eq!(
+        query[0].0,
+        "SELECT COUNT(*) FROM (SELECT `name` FROM `users` GROUP BY `name`) AS `t`"
+    );
+
+    // The offset overflows.
+    let page = Query::table("users")
+        .paginate::<(i64, String)>(&mut conn, u64::MAX, 20)
+        .await
+        .unwrap();
+    assert!(page.items.is_empty());
+    assert_eq!((page.total, page.page, page.last_page), (5, u64::MAX, 1));
+    let query = test::QUERY.take();
+    assert_eq!(query.len(), 1);
+
+    let page = Query::table("users")
+        .paginate::<(i64, String)>(&mut conn, 1, u64::MAX)
+        .await
+        .unwrap();
+    assert_eq!(page.items.len(), 5);
+    assert_eq!(page.last_page, 1);
+    let query = test::QUERY.take();
+    assert_eq!(
+        query[1].0,
+        "SELECT * FROM `users` LIMIT 9223372036854775807 OFFSET 0"
+    );
+}
+
+#[cfg(feature = "sqlite")]
+#[sqlx::test]
+async fn test_cursor_paginate() {
+    use sqlx::{Connection, SqliteConnection};
+
+    let _guard = LOCK.lock().unwrap();
+
+    let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+    conn.execute(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+        INSERT INTO users (name) VALUES ('a'),('b'),('b'),('c'),('d')",
+    )
+    .await
+    .unwrap();
+
+    let mut query = Query::table("users");
+    query.r#where("id<>4").order_by("name").order_by_desc("id");
+    let page = query
+        .cursor_paginate::<(i64, String)>(&mut conn, None, 2)
+        .await
+        .unwrap();
+    assert_eq!(page.items, [(1, "a".to_string()), (3, "b".to_string())]);
+    assert!(page.prev_cursor.is_none());
+    let next = page.next_cursor.unwrap();
+    let query_log = test::QUERY.take();
+    assert_eq!(
+        query_log[0].0,
+        "SELECT * FROM `users` WHERE id<>4 ORDER BY `name`,`id` DESC LIMIT 3"
+    );
+
+    let page = query
+        .cursor_paginate::<(i64, String)>(&mut conn, Some(&next), 2)
+        .await
+        .unwrap();
+    assert_eq!(page.items, [(2, "b".to_string()), (5, "d".to_string())]);
+    assert!(page.next_cursor.is_none());
+    let prev = page.prev_cursor.unwrap();
+    let query_log = test::QUERY.take();
+    assert_eq!(
+        query_log[0].0,
+        "SELECT * FROM `users` WHERE (id<>4) AND (`name`>? OR (`name`=? AND `id`<?)) \
+        ORDER BY `name`,`id` DESC LIMIT 3"
+    );
+    assert_eq!(&query_log[0].1, params![&"b", &"b", &3]);
+
+    let page = query
+        .cursor_paginate::<(i64, String)>(&mut conn, Some(&prev), 2)
+        .await
+        .unwrap();
+    assert_eq!(page.items, [(1, "a".to_string()), (3, "b".to_string())]);
+    assert!(page.prev_cursor.is_none());
+    assert_eq!(page.next_cursor.as_deref(), Some(next.as_str()));
+    let query_log = test::QUERY.take();
+    assert_eq!(
+        query_log[0].0,
+        "SELECT * FROM `users` WHERE (id<>4) AND (`name`<? OR (`name`=? AND `id`>?)) \
+        ORDER BY `name` DESC,`id` LIMIT 3"
+    );
+
+    let mut query = Query::table("users");
+    query.order_by("name").order_by("id");
+    let page = query
+        .cursor_paginate::<(i64, String)>(&mut conn, None, 3)
+        .await
+        .unwrap();
+    let page = query
+        .cursor_paginate::<(i64, String)>(&mut conn, page.next_cursor.as_deref(), 3)
+        .await
+        .unwrap();
+    assert_eq!(page.items, [(4, "c".to_string()), (5, "d".to_string())]);
+    let query_log = test::QUERY.take();
+    assert_eq!(
+        query_log[1].0,
+        "SELECT * FROM `users` WHERE (`name`,`id`)>(?,?) ORDER BY `name`,`id` LIMIT 4"
+    );
+    assert_eq!(&query_log[1].1, params![&"b", &3]);
+
+    let page = Query::table("users")
+        .order_by_desc("id")
+        .cursor_paginate::<(i64, String)>(&mut conn, Some(&next), 2)
+        .await;
+    assert!(matches!(page, Err(sorm::Error::InvalidCursor)));
+    let page = Query::table("users")
+        .cursor_paginate::<(i64, String)>(&mut conn, Some("!"), 2)
+        .await;
+    assert!(matches!(page, Err(sorm::Error::NoOrderBy)));
+
+    // Malformed cursors are rejected, never panicking.
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    let raw: [&[u8]; 13] = [
+        b"",
+        b"x",
+        b"ni1",
+        b"ni;",
+        b"ni99999999999999999999;",
+        b"nf1.5;",
+        b"ns",
+        b"ns18446744073709551615;",
+        b"ns99999999999999999999;",
+        b"ns1;",
+        b"ns2;\xff\xfe",
+        b"nz",
+        b"nzq",
+    ];
+    let mut cursors: Vec<String> = raw.iter().map(|v| URL_SAFE_NO_PAD.encode(v)).collect();
+    cursors.extend(["!", "a", "ab=", "\u{e9}", "bmk"].map(str::to_owned));
+    let mut query = Query::table("users");
+    query.order_by("id");
+    for cursor in &cursors {
+        let page = query
+            .cursor_paginate::<(i64, String)>(&mut conn, Some(cursor), 2)
+            .await;
+        assert!(
+            matches!(page, Err(sorm::Error::InvalidCursor)),
+            "{:?}",
+            cursor
+        );
+    }
+
+    // NULL keys cannot be stored in a cursor.
+    conn.execute("ALTER TABLE users ADD COLUMN rank INTEGER")
+        .await
+        .unwrap();
+    let page = Query::table("users")
+        .select(&["id", "rank"])
+        .order_by("rank")
+        .order_by("id")
+        .cursor_paginate::<(i64, Option<i64>)>(&mut conn, None, 2)
+        .await;
+    assert!(matches!(page, Err(sorm::Error::NullCursorColumn(column)) if column == "rank"));
+    test::QUERY.take();
+    assert_eq!(
+        sorm::Error::NullCursorColumn("rank".to_owned()).to_string(),
+        "cursor column rank is NULL"
+    );
+}
+
+#[sqlx::test]
+async fn test_fetch() {
+    use futures_util::TryStreamExt;
+
+    let _guard = LOCK.lock().unwrap();
+
+    let name = "foo";
+    let params;
+    let mut query = Query::table("users");
+    query.r#where(clause!("name={&name}", params));
+    let users: Vec<User> = query.fetch(DummyDB).try_collect().await.unwrap();
+    assert!(users.is_empty());
+    let query_log = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(query_log[0].0, "SELECT * FROM \"users\" WHERE name=$1");
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(query_log[0].0, "SELECT * FROM `users` WHERE name=?");
+    assert_eq!(&query_log[0].1, params![&"foo"]);
+
+    #[cfg(feature = "sqlite")]
+    {
+        use sqlx::{Connection, SqliteConnection};
+
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        conn.execute(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+            INSERT INTO users (name) VALUES ('foo'),('bar'),('foo')",
+        )
+        .await
+        .unwrap();
+
+        let users: Vec<(i64, String)> = query.fetch(&mut conn).try_collect().await.unwrap();
+        assert_eq!(users, [(1, "foo".to_string()), (3, "foo".to_string())]);
+
+        let mut query = Query::table("users");
+        query.select(&["id"]).order_by_desc("id");
+        let ids: Vec<i64> = query
+            .fetch_plunk::<i64>(&mut conn)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(ids, [3, 2, 1]);
+        test::QUERY.take();
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[sqlx::test]
+async fn test_chunk_by() {
+    use sorm::model::Model;
+    use sqlx::{Connection, SqliteConnection};
+
+    #[sorm(table = "users")]
+    struct Account {
+        #[sorm(primary_key(increment))]
+        id: i64,
+        name: String,
+    }
+
+    let _guard = LOCK.lock().unwrap();
+
+    let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+    conn.execute(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+        INSERT INTO users (name) VALUES ('a'),('b'),('b'),('c'),('d')",
+    )
+    .await
+    .unwrap();
+
+    let mut chunks = Vec::new();
+    Query::table("users")
+        .r#where("name<>'c'")
+        .order_by_desc("name")
+        .chunk_by(&mut conn, "id", 2, |rows: Vec<(i64, String)>| {
+            chunks.push(rows);
+            async { Ok(true) }
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        chunks,
+        [
+            vec![(1, "a".to_string()), (2, "b".to_string())],
+            vec![(3, "b".to_string()), (5, "d".to_string())],
+        ]
+    );
+    let query_log = test::QUERY.take();
+    assert_eq!(query_log.len(), 3);
+    assert_eq!(
+        query_log[0].0,
+        "SELECT * FROM `users` WHERE name<>'c' ORDER BY `id` LIMIT 2"
+    );
+    assert_eq!(
+        query_log[1].0,
+        "SELECT * FROM `users` WHERE (name<>'c') AND (`id`>?) ORDER BY `id` LIMIT 2"
+    );
+    assert_eq!(query_log[1].1, ["2"]);
+    assert_eq!(query_log[2].1, ["5"]);
+
+    let mut ids = Vec::new();
+    Account::query()
+        .chunk_by_id(&mut conn, 2, |accounts| {
+            ids.extend(accounts.iter().map(|v| *v.id().unwrap()));
+            let more = ids.len() < 4;
+            async move { Ok(more) }
+        })
+        .await
+        .unwrap();
+    assert_eq!(ids, [1, 2, 3, 4]);
+    let query_log = test::QUERY.take();
+    assert_eq!(query_log.len(), 2);
+    assert_eq!(
+        query_log[1].0,
+        "SELECT `id`,`name` FROM `users` WHERE `id`>? ORDER BY `id` LIMIT 2"
+    );
+}
+
+#[sqlx::test]
+async fn test_aggregate() {
+    let _guard = LOCK.lock().unwrap();
+
+    let mut query = Query::table("users");
+    query
+        .select(&["id", "name"])
+        .r#where("age>18")
+        .order_by("id")
+        .limit(10);
+    let _ = query.count(DummyDB).await;
+    let _ = query.exists(DummyDB).await;
+    let _ = query.sum::<i64>(DummyDB, "age").await;
+    let _ = query.avg::<f64>(DummyDB, "age").await;
+    let _ = query.max::<i32>(DummyDB, "age").await;
+    let query_log: Vec<_> = test::QUERY.take().into_iter().map(|v| v.0).collect();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query_log,
+        [
+            "SELECT COUNT(*) FROM \"users\" WHERE age>18",
+            "SELECT EXISTS(SELECT 1 FROM \"users\" WHERE age>18)",
+            "SELECT CAST(COALESCE(SUM(\"age\"),0) AS BIGINT) FROM \"users\" WHERE age>18",
+            "SELECT CAST(AVG(\"age\") AS DOUBLE PRECISION) FROM \"users\" WHERE age>18",
+            "SELECT MAX(\"age\") FROM \"users\" WHERE age>18",
+        ]
+    );
+    #[cfg(feature = "mysql")]
+    assert_eq!(
+        query_log,
+        [
+            "SELECT COUNT(*) FROM `users` WHERE age>18",
+            "SELECT EXISTS(SELECT 1 FROM `users` WHERE age>18)",
+            "SELECT CAST(COALESCE(SUM(`age`),0) AS SIGNED) FROM `users` WHERE age>18",
+            "SELECT CAST(AVG(`age`) AS DOUBLE) FROM `users` WHERE age>18",
+            "SELECT MAX(`age`) FROM `users` WHERE age>18",
+        ]
+    );
+    #[cfg(feature = "sqlite")]
+    assert_eq!(
+        query_log,
+        [
+            "SELECT COUNT(*) FROM `users` WHERE age>18",
+            "SELECT EXISTS(SELECT 1 FROM `users` WHERE age>18)",
+            "SELECT CAST(COALESCE(SUM(`age`),0) AS INTEGER) FROM `users` WHERE age>18",
+            "SELECT CAST(AVG(`age`) AS REAL) FROM `users` WHERE age>18",
+            "SELECT MAX(`age`) FROM `users` WHERE age>18",
+        ]
+    );
+
+    let mut query = Query::table("users");
+    query.select_raw("name,COUNT(0) AS num").group_by(&["name"]);
+    let _ = query.count(DummyDB).await;
+    let _ = query.exists(DummyDB).await;
+    let _ = query.min::<i64>(DummyDB, "num").await;
+    let query_log: Vec<_> = test::QUERY.take().into_iter().map(|v| v.0).collect();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query_log,
+        [
+            "SELECT COUNT(*) FROM (SELECT name,COUNT(0) AS num FROM \"users\" GROUP BY \"name\") AS \"t\"",
+            "SELECT EXISTS(SELECT name,COUNT(0) AS num FROM \"users\" GROUP BY \"name\")",
+            "SELECT MIN(\"num\") FROM (SELECT name,COUNT(0) AS num FROM \"users\" GROUP BY \"name\") AS \"t\"",
+        ]
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query_log,
+        [
+            "SELECT COUNT(*) FROM (SELECT name,COUNT(0) AS num FROM `users` GROUP BY `name`) AS `t`",
+            "SELECT EXISTS(SELECT name,COUNT(0) AS num FROM `users` GROUP BY `name`)",
+            "SELECT MIN(`num`) FROM (SELECT name,COUNT(0) AS num FROM `users` GROUP BY `name`) AS `t`",
+        ]
+    );
+
+    #[cfg(feature = "sqlite")]
+    {
+        use sqlx::{Connection, SqliteConnection};
+
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        conn.execute(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, age INTEGER NOT NULL);
+            INSERT INTO users (name, age) VALUES ('a',10),('b',20),('b',25),('c',30)",
+        )
+        .await
+        .unwrap();
+
+        let mut adult = Query::table("users");
+        adult.select(&["name"]).r#where("age>18");
+        assert_eq!(adult.count(&mut conn).await.unwrap(), 3);
+        assert!(adult.exists(&mut conn).await.unwrap());
+        assert_eq!(adult.sum::<i64>(&mut conn, "age").await.unwrap(), 75);
+        assert_eq!(
+            adult.avg::<f64>(&mut conn, "age").await.unwrap(),
+            Some(25.0)
+        );
+        assert_eq!(
+            adult
+                .min::<String>(&mut conn, "name")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("b")
+        );
+        assert_eq!(adult.max::<i64>(&mut conn, "age").await.unwrap(), Some(30));
+        assert_eq!(query.count(&mut conn).await.unwrap(), 3);
+        assert_eq!(query.max::<i64>(&mut conn, "num").await.unwrap(), Some(2));
+
+        let mut none = Query::table("users");
+        none.r#where("age>100");
+        assert_eq!(none.count(&mut conn).await.unwrap(), 0);
+        assert!(!none.exists(&mut conn).await.unwrap());
+        assert_eq!(none.sum::<i64>(&mut conn, "age").await.unwrap(), 0);
+        assert_eq!(none.avg::<f64>(&mut conn, "age").await.unwrap(), None);
+        assert_eq!(none.max::<i64>(&mut conn, "age").await.unwrap(), None);
+        test::QUERY.take();
+    }
+}
+
+#[sqlx::test]
+async fn test_insert() {
+    use sorm::query::Insert;
+
+    let _guard = LOCK.lock().unwrap();
+
+    let (foo, bar) = ("foo", "bar");
+    let insert = Insert::new()
+        .set("name", &foo)
+        .set_raw("age", "18")
+        .row()
+        .set("name", &bar)
+        .set_raw("age", "20");
+    let _ = Query::table("users").insert(DummyDB, &insert).await;
+    let query_log = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query_log[0].0,
+        "INSERT INTO \"users\" (\"name\",\"age\") VALUES ($1,18),($2,20)"
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query_log[0].0,
+        "INSERT INTO `users` (`name`,`age`) VALUES (?,18),(?,20)"
+    );
+    assert_eq!(&query_log[0].1, params![&"foo", &"bar"]);
+
+    // The alias is left out.
+    let _ = Query::table("users")
+        .alias("u")
+        .insert(DummyDB, &Insert::new().set("name", &foo))
+        .await;
+    let query_log = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(query_log[0].0, "INSERT INTO \"users\" (\"name\") VALUES ($1)");
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(query_log[0].0, "INSERT INTO `users` (`name`) VALUES (?)");
+
+    let _ = Query::table("users").insert(DummyDB, &Insert::new()).await;
+    let query_log = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(query_log[0].0, "INSERT INTO \"users\" DEFAULT VALUES");
+    #[cfg(feature = "mysql")]
+    assert_eq!(query_log[0].0, "INSERT INTO `users` () VALUES ()");
+    #[cfg(feature = "sqlite")]
+    assert_eq!(query_log[0].0, "INSERT INTO `users` DEFAULT VALUES");
+
+    let age = 18;
+    let params;
+    let mut query = Query::table("users");
+    query
+        .select(&["id", "name"])
+        .r#where(clause!("age>{age}", params));
+    let insert = Insert::select(&["id", "name"], &query);
+    let _ = Query::table("archives")
+        .insert_get_id(DummyDB, &insert, "id")
+        .await;
+    let query_log = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query_log[0].0,
+        "INSERT INTO \"archives\" (\"id\",\"name\") SELECT \"id\",\"name\" FROM \"users\" \
+        WHERE age>$1 RETURNING CAST(\"id\" AS BIGINT)"
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query_log[0].0,
+        "INSERT INTO `archives` (`id`,`name`) SELECT `id`,`name` FROM `users` WHERE age>?"
+    );
+    assert_eq!(&query_log[0].1, params![&18]);
+
+    #[cfg(feature = "sqlite")]
+    {
+        use sqlx::{Connection, SqliteConnection};
+
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        conn.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .await
+            .unwrap();
+
+        let insert = Insert::new().set("name", &foo).row().set("name", &bar);
+        let users = Query::table("users");
+        assert_eq!(users.insert(&mut conn, &insert).await.unwrap(), 2);
+        let insert = Insert::new().set("name", &foo);
+        assert_eq!(
+            users.insert_get_id(&mut conn, &insert, "id").await.unwrap(),
+            3
+        );
+        // The id of the last row of a multi-row insert.
+        let many = Insert::new().set("name", &foo).row().set("name", &bar);
+        assert_eq!(
+            users.insert_get_id(&mut conn, &many, "id").await.unwrap(),
+            5
+        );
+        let rows: Vec<(i64, String)> = users
+            .insert_returning(&mut conn, &insert, &["id", "name"])
+            .await
+            .unwrap();
+        assert_eq!(rows, [(6, "foo".to_string())]);
+        test::QUERY.take();
+    }
+}
+
+#[sqlx::test]
+async fn test_insert_invalid() {
+    use sorm::query::Insert;
+
+    let (foo, age) = ("foo", 18);
+    let users = Query::table("users");
+    let insert = Insert::new()
+        .set("name", &foo)
+        .set("age", &age)
+        .row()
+        .set("age", &age)
+        .set("name", &foo);
+    assert!(matches!(
+        users.insert(DummyDB, &insert).await,
+        Err(sorm::Error::InvalidInsert(
+            "the columns must be set in the same order as in the first row"
+        ))
+    ));
+    let insert = Insert::new().set("name", &foo).row().row();
+    assert!(matches!(
+        users.insert_get_id(DummyDB, &insert, "id").await,
+        Err(sorm::Error::InvalidInsert(
+            "all the columns must be set before starting a new row"
+        ))
+    ));
+    let insert = Insert::new()
+        .set("name", &foo)
+        .set("age", &age)
+        .row()
+        .set("name", &foo);
+    assert!(matches!(
+        users.insert(DummyDB, &insert).await,
+        Err(sorm::Error::InvalidInsert(
+            "all the columns must be set in the last row"
+        ))
+    ));
+    let insert = Insert::select(&["name"], &users).set("name", &foo);
+    assert!(matches!(
+        users.insert(DummyDB, &insert).await,
+        Err(sorm::Error::InvalidInsert(
+            "cannot set values of an INSERT ... SELECT"
+        ))
+    ));
+}
+
+#[sqlx::test]
+async fn test_lock() {
+    let _guard = LOCK.lock().unwrap();
+
+    let _ = Query::table("jobs")
+        .r#where("status=0")
+        .order_by("id")
+        .limit(10)
+        .lock_for_update()
+        .skip_locked()
+        .get::<User>(DummyDB)
+        .await;
+    let _ = Query::table("jobs")
+        .r#where("id=1")
+        .lock_for_share()
+        .nowait()
+        .find::<User>(DummyDB)
+        .await;
+    let _ = Query::table("jobs")
+        .skip_locked()
+        .get::<User>(DummyDB)
+        .await;
+    let query_log: Vec<_> = test::QUERY.take().into_iter().map(|v| v.0).collect();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query_log,
+        [
+            "SELECT * FROM \"jobs\" WHERE status=0 ORDER BY \"id\" LIMIT 10 FOR UPDATE SKIP LOCKED",
+            "SELECT * FROM \"jobs\" WHERE id=1 LIMIT 1 FOR SHARE NOWAIT",
+            "SELECT * FROM \"jobs\"",
+        ]
+    );
+    #[cfg(feature = "mysql")]
+    assert_eq!(
+        query_log,
+        [
+            "SELECT * FROM `jobs` WHERE status=0 ORDER BY `id` LIMIT 10 FOR UPDATE SKIP LOCKED",
+            "SELECT * FROM `jobs` WHERE id=1 LIMIT 1 FOR SHARE NOWAIT",
+            "SELECT * FROM `jobs`",
+        ]
+    );
+    #[cfg(feature = "sqlite")]
+    assert_eq!(
+        query_log,
+        [
+            "SELECT * FROM `jobs` WHERE status=0 ORDER BY `id` LIMIT 10",
+            "SELECT * FROM `jobs` WHERE id=1 LIMIT 1",
+            "SELECT * FROM `jobs`",
+        ]
+    );
+
+    // The lock applies to the whole statement, never to compound members, CTEs or subqueries.
+    let mut archive = Query::table("archived_jobs");
+    archive.lock_for_update();
+    let mut running = Query::table("workers");
+    running.r#where("status=1").lock_for_share();
+    let mut totals = Query::table("jobs");
+    totals.lock_for_update();
+    let _ = Query::table("jobs")
+        .with("totals", &totals)
+        .where_exists(&running)
+        .lock_for_update()
+        .get::<User>(DummyDB)
+        .await;
+    let sql = Query::table("jobs").union(&archive).to_sql().sql;
+    let query_log = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query_log[0].0,
+        "WITH \"totals\" AS (SELECT * FROM \"jobs\") SELECT * FROM \"jobs\" \
+        WHERE EXISTS (SELECT * FROM \"workers\" WHERE status=1) FOR UPDATE"
+    );
+    #[cfg(feature = "mysql")]
+    assert_eq!(
+        query_log[0].0,
+        "WITH `totals` AS (SELECT * FROM `jobs`) SELECT * FROM `jobs` \
+        WHERE EXISTS (SELECT * FROM `workers` WHERE status=1) FOR UPDATE"
+    );
+    #[cfg(feature = "sqlite")]
+    assert_eq!(
+        query_log[0].0,
+        "WITH `totals` AS (SELECT * FROM `jobs`) SELECT * FROM `jobs` \
+        WHERE EXISTS (SELECT * FROM `workers` WHERE status=1)"
+    );
+    #[cfg(feature = "postgres")]
+    assert_eq!(sql, "SELECT * FROM \"jobs\" UNION SELECT * FROM \"archived_jobs\"");
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(sql, "SELECT * FROM `jobs` UNION SELECT * FROM `archived_jobs`");
+}
+
+#[sqlx::test]
+async fn test_raw_params() {
+    let _guard = LOCK.lock().unwrap();
+
+    let (fallback, name, bucket, min, max) = ("none", "foo", 10, 1, 2);
+    let (select, group, order) = (
+        clause!("COALESCE(name,{&fallback}) AS name,COUNT(0)"),
+        clause!("name,age/{bucket}"),
+        clause!("similarity(name,{&name}) DESC"),
+    );
+    let (by_age, by_count) = (clause!("age>{min}"), clause!("COUNT(0)>{max}"));
+    let _ = Query::table("users")
+        .select_raw(select)
+        .r#where(by_age)
+        .group_by_raw(group)
+        .having(by_count)
+        .order_by_raw(order)
+        .get::<User>(DummyDB)
+        .await;
+    let query_log = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query_log[0].0,
+        "SELECT COALESCE(name,$1) AS name,COUNT(0) FROM \"users\" WHERE age>$2 \
+        GROUP BY name,age/$3 HAVING COUNT(0)>$4 ORDER BY similarity(name,$5) DESC"
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query_log[0].0,
+        "SELECT COALESCE(name,?) AS name,COUNT(0) FROM `users` WHERE age>? \
+        GROUP BY name,age/? HAVING COUNT(0)>? ORDER BY similarity(name,?) DESC"
+    );
+    assert_eq!(&query_log[0].1, params![&"none", &1, &10, &2, &"foo"]);
+
+    let mut query = Query::table("users");
+    query.distinct().select(&["name"]);
+    let _ = query.get::<User>(DummyDB).await;
+    let _ = query.count(DummyDB).await;
+    let query_log: Vec<_> = test::QUERY.take().into_iter().map(|v| v.0).collect();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query_log,
+        [
+            "SELECT DISTINCT \"name\" FROM \"users\"",
+            "SELECT COUNT(*) FROM (SELECT DISTINCT \"name\" FROM \"users\") AS \"t\"",
+        ]
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query_log,
+        [
+            "SELECT DISTINCT `name` FROM `users`",
+            "SELECT COUNT(*) FROM (SELECT DISTINCT `name` FROM `users`) AS `t`",
+        ]
+    );
+
+    #[cfg(feature = "postgres")]
+    {
+        let _ = Query::table("orders")
+            .distinct_on(&["user_id"])
+            .order_by("user_id")
+            .order_by_desc("created_at")
+            .get::<User>(DummyDB)
+            .await;
+        let query_log = test::QUERY.take();
+        assert_eq!(
+            query_log[0].0,
+            "SELECT DISTINCT ON (\"user_id\") * FROM \"orders\" ORDER BY \"user_id\",\"created_at\" DESC"
+        );
+    }
+}
+
+#[sqlx::test]
+async fn test_with() {
+    let _guard = LOCK.lock().unwrap();
+
+    let (root, min, status) = (1, 100, 2);
+    let (by_root, by_total, by_status) = (
+        clause!("id={root}"),
+        clause!("total>{min}"),
+        clause!("status={status}"),
+    );
+    let mut anchor = Query::table("categories");
+    anchor.select(&["id", "parent_id"]).r#where(by_root);
+    let mut recursive = Query::table(("categories", "c"));
+    recursive
+        .select(&["c.id", "c.parent_id"])
+        .join(("tree", "t"), "c.parent_id=t.id");
+    let mut totals = Query::table("orders");
+    totals
+        .select_raw("category_id,SUM(amount) AS total")
+        .r#where(by_status)
+        .group_by(&["category_id"]);
+    let mut query = Query::table("tree");
+    query
+        .with_recursive("tree", &anchor, &recursive)
+        .with("totals", &totals)
+        .join("totals", "totals.category_id=tree.id")
+        .r#where(by_total);
+    let _ = query.get::<User>(DummyDB).await;
+    let _ = query.count(DummyDB).await;
+    let query_log = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    {
+        assert_eq!(
+            query_log[0].0,
+            "WITH RECURSIVE \"tree\" AS (SELECT \"id\",\"parent_id\" FROM \"categories\" WHERE id=$1 \
+            UNION ALL SELECT \"c\".\"id\",\"c\".\"parent_id\" FROM \"categories\" AS \"c\" \
+            INNER JOIN \"tree\" AS \"t\" ON c.parent_id=t.id),\
+            \"totals\" AS (SELECT category_id,SUM(amount) AS total FROM \"orders\" \
+            WHERE status=$2 GROUP BY \"category_id\") \
+            SELECT * FROM \"tree\" INNER JOIN \"totals\" ON totals.category_id=tree.id WHERE total>$3"
+        );
+        assert!(query_log[1].0.ends_with(
+            "SELECT COUNT(*) FROM \"tree\" INNER JOIN \"totals\" ON totals.category_id=tree.id WHERE total>$3"
+        ));
+    }
+    #[cfg(not(feature = "postgres"))]
+    {
+        assert_eq!(
+            query_log[0].0,
+            "WITH RECURSIVE `tree` AS (SELECT `id`,`parent_id` FROM `categories` WHERE id=? \
+            UNION ALL SELECT `c`.`id`,`c`.`parent_id` FROM `categories` AS `c` \
+            INNER JOIN `tree` AS `t` ON c.parent_id=t.id),\
+            `totals` AS (SELECT category_id,SUM(amount) AS total FROM `orders` \
+            WHERE status=? GROUP BY `category_id`) \
+            SELECT * FROM `tree` INNER JOIN `totals` ON totals.category_id=tree.id WHERE total>?"
+        );
+        assert!(query_log[1].0.ends_with(
+            "SELECT COUNT(*) FROM `tree` INNER JOIN `totals` ON totals.category_id=tree.id WHERE total>?"
+        ));
+    }
+    assert_eq!(&query_log[0].1, params![&1, &2, &100]);
+    assert_eq!(&query_log[1].1, params![&1, &2, &100]);
+
+    #[cfg(feature = "sqlite")]
+    {
+        use sqlx::{Connection, SqliteConnection};
+
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        conn.execute(
+            "CREATE TABLE categories (id INTEGER PRIMARY KEY, parent_id INTEGER);
+            INSERT INTO categories (id, parent_id) VALUES (1,NULL),(2,1),(3,2),(4,NULL),(5,1)",
+        )
+        .await
+        .unwrap();
+
+        let mut query = Query::table("tree");
+        query
+            .with_recursive("tree", &anchor, &recursive)
+            .select(&["id"])
+            .order_by("id");
+        let ids: Vec<i64> = query.plunk(&mut conn).await.unwrap();
+        assert_eq!(ids, [1, 2, 3, 5]);
+        test::QUERY.take();
+    }
+}
+
+fn search_owned(name: String, statuses: Vec<i32>) -> QueryOwned {
+    let mut paid = QueryOwned::table("orders");
+    paid.select(&["user_id"])
+        .r#where(ClauseOwned::new("amount>?").bind(100));
+    let mut clause = ClauseOwned::new(format!(
+        "status IN ({})",
+        vec!["?"; statuses.len()].join(",")
+    ));
+    for status in statuses {
+        clause = clause.bind(status);
+    }
+    let mut query = QueryOwned::table(("users", "u"));
+    query
+        .select(&["u.id", "u.name"])
+        .r#where(ClauseOwned::new("name=?").bind(name))
+        .where_group(|query| {
+            query.r#where(clause).or_where("status IS NULL");
+        })
+        .where_in_sub("id", paid)
+        .order_by_raw(ClauseOwned::new("age>? DESC").bind(18))
+        .limit(10);
+    query
+}
+
+#[sqlx::test]
+async fn test_query_owned() {
+    let _guard = LOCK.lock().unwrap();
+
+    fn assert_owned<T: Clone + Send + Sync + 'static>(_: &T) {}
+
+    let query = search_owned("foo".to_string(), vec![1, 2]);
+    assert_owned(&query);
+    let query = std::thread::spawn(move || query).join().unwrap();
+    let mut copied = query.clone();
+    copied.offset(10);
+    let _ = query.as_query().get::<User>(DummyDB).await;
+    let _ = copied.as_query().count(DummyDB).await;
+    let query_log = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    {
+        assert_eq!(
+            query_log[0].0,
+            "SELECT \"u\".\"id\",\"u\".\"name\" FROM \"users\" AS \"u\" \
+            WHERE (name=$1) AND ((status IN ($2,$3)) OR (status IS NULL)) \
+            AND \"id\" IN (SELECT \"user_id\" FROM \"orders\" WHERE amount>$4) \
+            ORDER BY age>$5 DESC LIMIT 10"
+        );
+        assert_eq!(
+            query_log[1].0,
+            "SELECT COUNT(*) FROM \"users\" AS \"u\" \
+            WHERE (name=$1) AND ((status IN ($2,$3)) OR (status IS NULL)) \
+            AND \"id\" IN (SELECT \"user_id\" FROM \"orders\" WHERE amount>$4)"
+        );
+    }
+    #[cfg(not(feature = "postgres"))]
+    {
+        assert_eq!(
+            query_log[0].0,
+            "SELECT `u`.`id`,`u`.`name` FROM `users` AS `u` \
+            WHERE (name=?) AND ((status IN (?,?)) OR (status IS NULL)) \
+            AND `id` IN (SELECT `user_id` FROM `orders` WHERE amount>?) \
+            ORDER BY age>? DESC LIMIT 10"
+        );
+        assert_eq!(
+            query_log[1].0,
+            "SELECT COUNT(*) FROM `users` AS `u` \
+            WHERE (name=?) AND ((status IN (?,?)) OR (status IS NULL)) \
+            AND `id` IN (SELECT `user_id` FROM `orders` WHERE amount>?)"
+        );
+    }
+    assert_eq!(
+        &query_log[0].1,
+        params![&"foo".to_string(), &1, &2, &100, &18]
+    );
+    assert_eq!(
+        &query_log[1].1,
+        params![&"foo".to_string(), &1, &2, &100]
+    );
+
+    let allowed = ["name", "deleted_at"];
+    let mut query = QueryOwned::table("users");
+    query
+        .order_by_checked("-deleted_at:nulls_last,name", &allowed)
+        .unwrap();
+    assert!(query.clone().order_by_checked("password", &allowed).is_err());
+    #[cfg(feature = "sqlite")]
+    assert_eq!(
+        query.as_query().to_sql().sql,
+        "SELECT * FROM `users` ORDER BY `deleted_at` DESC NULLS LAST,`name`"
+    );
+    #[cfg(feature = "mysql")]
+    assert_eq!(
+        query.as_query().to_sql().sql,
+        "SELECT * FROM `users` ORDER BY `deleted_at` IS NULL,`deleted_at` DESC,`name`"
+    );
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query.as_query().to_sql().sql,
+        "SELECT * FROM \"users\" ORDER BY \"deleted_at\" DESC NULLS LAST,\"name\""
+    );
+
+    // Models have no owned query, the rows are decoded with the generic methods of `Query`.
+    use sorm::model::Model;
+    #[sorm(table = "members")]
+    struct Member {
+        #[sorm(primary_key(increment))]
+        id: i64,
+        name: String,
+    }
+    let mut query = QueryOwned::table(Member::TABLE);
+    query.select(Member::COLUMNS).order_by("name");
+    let _ = query.as_query().get::<Member>(DummyDB).await;
+    let query_log = test::QUERY.take();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query_log[0].0,
+        "SELECT \"id\",\"name\" FROM \"members\" ORDER BY \"name\""
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query_log[0].0,
+        "SELECT `id`,`name` FROM `members` ORDER BY `name`"
+    );
+
+    #[cfg(feature = "sqlite")]
+    {
+        use sqlx::{Connection, SqliteConnection};
+
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        conn.execute(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, status INTEGER, age INTEGER);
+            CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER, amount INTEGER);
+            INSERT INTO users (id, name, status, age) VALUES
+                (1,'foo',1,10),(2,'foo',NULL,20),(3,'foo',3,30),(4,'bar',1,40),(5,'foo',2,50);
+            INSERT INTO orders (user_id, amount) VALUES (1,200),(2,300),(3,400),(4,500),(5,50)",
+        )
+        .await
+        .unwrap();
+
+        let mut query = search_owned("foo".to_string(), vec![1, 2]);
+        query.select(&["u.id"]);
+        let ids: Vec<i64> = query.as_query().plunk(&mut conn).await.unwrap();
+        assert_eq!(ids, [2, 1]);
+        test::QUERY.take();
+    }
+}
+
+#[sqlx::test]
+async fn test_to_sql() {
+    let _guard = LOCK.lock().unwrap();
+
+    let (status, name) = (1, "foo");
+    let (by_status, set_name) = (clause!("status={status}"), clause!("name={&name}"));
+    let mut query = Query::table("users");
+    query.select(&["id"]).r#where(by_status).limit(10);
+    let select = query.to_sql();
+    let update = query.to_update_sql(set_name).unwrap();
+    let delete = query.to_delete_sql().unwrap();
+    assert!(test::QUERY.take().is_empty());
+    #[cfg(feature = "postgres")]
+    {
+        assert_eq!(
+            select.sql,
+            "SELECT \"id\" FROM \"users\" WHERE status=$1 LIMIT 10"
+        );
+        assert_eq!(
+            update.sql,
+            "UPDATE \"users\" SET name=$1 WHERE status=$2"
+        );
+        assert_eq!(delete.sql, "DELETE FROM \"users\" WHERE status=$1");
+        assert_eq!(
+            select.to_string(),
+            "SELECT \"id\" FROM \"users\" WHERE status=$1 LIMIT 10 -- [1]"
+        );
+    }
+    #[cfg(not(feature = "postgres"))]
+    {
+        assert_eq!(select.sql, "SELECT `id` FROM `users` WHERE status=? LIMIT 10");
+        assert_eq!(update.sql, "UPDATE `users` SET name=? WHERE status=?");
+        assert_eq!(delete.sql, "DELETE FROM `users` WHERE status=?");
+        assert_eq!(
+            select.to_string(),
+            "SELECT `id` FROM `users` WHERE status=? LIMIT 10 -- [1]"
+        );
+    }
+    assert_eq!(select.params, ["1"]);
+    assert_eq!(update.params, ["\"foo\"", "1"]);
+    assert_eq!(delete.params, ["1"]);
+
+    let _ = query.explain(DummyDB).await;
+    let query_log = test::QUERY.take();
+    #[cfg(feature = "sqlite")]
+    assert_eq!(
+        query_log[0].0,
+        "EXPLAIN QUERY PLAN SELECT `id` FROM `users` WHERE status=? LIMIT 10"
+    );
+    #[cfg(feature = "mysql")]
+    assert_eq!(
+        query_log[0].0,
+        "EXPLAIN SELECT `id` FROM `users` WHERE status=? LIMIT 10"
+    );
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query_log[0].0,
+        "EXPLAIN SELECT \"id\" FROM \"users\" WHERE status=$1 LIMIT 10"
+    );
+    assert_eq!(&query_log[0].1, params![&1]);
+
+    // The statements that would not be executed are errors.
+    let all = Query::table("users");
+    assert!(matches!(
+        all.to_update_sql("name=''"),
+        Err(sorm::Error::NoWhereClause)
+    ));
+    assert!(matches!(
+        all.to_delete_sql(),
+        Err(sorm::Error::NoWhereClause)
+    ));
+    let mut joined = Query::table("users");
+    joined
+        .join("orders", "orders.user_id=users.id")
+        .r#where("orders.amount>0");
+    assert!(matches!(
+        joined.to_update_sql("name=''"),
+        Err(sorm::Error::UnsupportedJoin)
+    ));
+    assert!(matches!(
+        joined.to_delete_sql(),
+        Err(sorm::Error::UnsupportedJoin)
+    ));
+
+    #[cfg(feature = "sqlite")]
+    {
+        use sqlx::{Connection, Row, SqliteConnection};
+
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        conn.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, status INTEGER)")
+            .await
+            .unwrap();
+        let by_id = clause!("id={status}");
+        let mut query = Query::table("users");
+        query.r#where(by_id);
+        let rows = query.explain(&mut conn).await.unwrap();
+        let detail: String = rows[0].try_get("detail").unwrap();
+        assert!(detail.contains("users"), "{}", detail);
+        test::QUERY.take();
+    }
+}
+
+#[cfg(feature = "postgres")]
+#[sqlx::test]
+async fn test_pg_placeholder() {
+    let _guard = LOCK.lock().unwrap();
+
+    let (tag, name, id) = ("a", "what?", 1);
+    let (by_tag, by_id) = (
+        clause!("tags ??| ARRAY[{&tag}] AND data ?? 'key?' AND note<>E'it\\'s?'"),
+        clause!("\"why?\"={id} /* a? /* b? */ c? */ AND $$x?$$<>$t$?$t$ -- ?\nAND x=$1"),
+    );
+    let update = Update::new()
+        .set("name?", &name)
+        .set_raw("note", "'?'")
+        .set("id", &id);
+    let mut query = Query::table("users");
+    query.r#where(by_tag).r#where(by_id);
+    let _ = query.update(DummyDB, &update).await;
+    let query_log = test::QUERY.take();
+    assert_eq!(
+        query_log[0].0,
+        "UPDATE \"users\" SET \"name?\"=$1,\"note\"='?',\"id\"=$2 \
+        WHERE (tags ?| ARRAY[$3] AND data ? 'key?' AND note<>E'it\\'s?') \
+        AND (\"why?\"=$4 /* a? /* b? */ c? */ AND $$x?$$<>$t$?$t$ -- ?\nAND x=$1)"
+    );
+    assert_eq!(&query_log[0].1, params![&"what?", &1, &"a", &1]);
+}
+
+#[sqlx::test]
+async fn test_order_by_checked() {
+    let allowed = ["name", "created_at", "deleted_at"];
+    let mut query = Query::table("users");
+    query
+        .order_by_checked(
+            "-created_at:nulls_last,name,deleted_at:nulls_first",
+            &allowed,
+        )
+        .unwrap();
+    #[cfg(feature = "sqlite")]
+    assert_eq!(
+        query.to_sql().sql,
+        "SELECT * FROM `users` ORDER BY `created_at` DESC NULLS LAST,`name`,`deleted_at` NULLS FIRST"
+    );
+    #[cfg(feature = "mysql")]
+    assert_eq!(
+        query.to_sql().sql,
+        "SELECT * FROM `users` ORDER BY `created_at` IS NULL,`created_at` DESC,`name`,\
+        `deleted_at` IS NULL DESC,`deleted_at`"
+    );
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query.to_sql().sql,
+        "SELECT * FROM \"users\" ORDER BY \"created_at\" DESC NULLS LAST,\"name\",\
+        \"deleted_at\" NULLS FIRST"
+    );
+
+    let mut query = Query::table("users");
+    let error = |query: &mut Query, input| match query.order_by_checked(input, &allowed) {
+        Err(sorm::Error::InvalidOrderBy(message)) => message,
+        _ => panic!("expected an error"),
+    };
+    assert_eq!(
+        error(&mut query, "name,password"),
+        "column `password` is not allowed"
+    );
+    assert_eq!(
+        error(&mut query, "name;DROP TABLE users"),
+        "column `name;DROP TABLE users` is not allowed"
+    );
+    assert_eq!(error(&mut query, "-"), "column `` is not allowed");
+    assert_eq!(
+        error(&mut query, "name:nulls"),
+        "invalid ordering `name:nulls`"
+    );
+    #[cfg(feature = "postgres")]
+    assert_eq!(query.to_sql().sql, "SELECT * FROM \"users\"");
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(query.to_sql().sql, "SELECT * FROM `users`");
+
+    // The whitespace around the entries is ignored.
+    let mut query = Query::table("users");
+    query.order_by_checked(" name, -created_at ,", &allowed).unwrap();
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        query.to_sql().sql,
+        "SELECT * FROM \"users\" ORDER BY \"name\",\"created_at\" DESC"
+    );
+    #[cfg(not(feature = "postgres"))]
+    assert_eq!(
+        query.to_sql().sql,
+        "SELECT * FROM `users` ORDER BY `name`,`created_at` DESC"
+    );
+
+    #[cfg(feature = "sqlite")]
+    {
+        use sqlx::{Connection, SqliteConnection};
+
+        let _guard = LOCK.lock().unwrap();
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        conn.execute(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, created_at INTEGER);
+            INSERT INTO users (id, created_at) VALUES (1,10),(2,NULL),(3,30)",
+        )
+        .await
+        .unwrap();
+        let mut query = Query::table("users");
+        query.select(&["id"]);
+        query
+            .order_by_checked("-created_at:nulls_last", &["created_at"])
+            .unwrap();
+        let ids: Vec<i64> = query.plunk(&mut conn).await.unwrap();
+        assert_eq!(ids, [3, 1, 2]);
+        let mut query = Query::table("users");
+        query.select(&["id"]);
+        query
+            .order_by_checked("-created_at:nulls_first", &["created_at"])
+            .unwrap();
+        let ids: Vec<i64> = query.plunk(&mut conn).await.unwrap();
+        assert_eq!(ids, [2, 3, 1]);
+        test::QUERY.take();
+    }
+}
@@ -0,0 +1,200 @@
+use sorm::model::Model;
+use sorm::schema::{compare, Column};
+use sorm::sorm;
+
+#[sorm(table = "users")]
+struct User {
+    #[sorm(primary_key(increment))]
+    id: i64,
+    name: String,
+    email: Option<String>,
+}
+
+fn column(name: &str, type_name: &str, nullable: bool) -> Column {
+    Column {
+        name: name.to_string(),
+        type_name: type_name.to_string(),
+        nullable,
+        primary_key: false,
+        increment: false,
+    }
+}
+
+#[test]
+fn test_describe() {
+    let columns = User::describe();
+    assert_eq!(columns.len(), 3);
+    assert_eq!(columns[0].name, "id");
+    assert!(columns[0].primary_key);
+    assert!(columns[0].increment);
+    assert!(!columns[0].nullable);
+    assert!(!columns[1].primary_key);
+    assert!(!columns[1].nullable);
+    assert!(columns[2].nullable);
+
+    #[cfg(feature = "sqlite")]
+    assert_eq!(columns[0].type_name, "INTEGER");
+    #[cfg(feature = "postgres")]
+    assert_eq!(columns[0].type_name, "INT8");
+    #[cfg(feature = "mysql")]
+    assert_eq!(columns[0].type_name, "BIGINT");
+}
+
+#[test]
+fn test_compare() {
+    let expected = User::describe();
+
+    let diff = compare(User::TABLE, &expected, &expected);
+    assert!(diff.is_empty());
+    assert!(diff.alter_statements().is_empty());
+
+    #[cfg(feature = "sqlite")]
+    let actual = [
+        column("id", "INTEGER", false),
+        column("name", "VARCHAR(255)", true),
+        column("age", "INT", false),
+    ];
+    #[cfg(feature = "postgres")]
+    let actual = [
+        column("id", "int8", false),
+        column("name", "varchar", true),
+        column("age", "int4", false),
+    ];
+    #[cfg(feature = "mysql")]
+    let actual = [
+        column("id", "bigint(20)", false),
+        column("name", "varchar(255)", true),
+        column("age", "int(11)", false),
+    ];
+
+    let diff = compare(User::TABLE, &expected, &actual);
+    assert!(!diff.is_empty());
+    assert_eq!(diff.missing.len(), 1);
+    assert_eq!(diff.missing[0].name, "email");
+    assert_eq!(diff.extra.len(), 1);
+    assert_eq!(diff.extra[0].name, "age");
+    assert_eq!(diff.mistyped.len(), 1);
+    assert_eq!(diff.mistyped[0].0.name, "name");
+
+    let statements = diff.alter_statements();
+    #[cfg(feature = "sqlite")]
+    assert_eq!(
+        statements,
+        [
+            "ALTER TABLE `users` ADD COLUMN `email` TEXT",
+            "ALTER TABLE `users` DROP COLUMN `age`",
+        ]
+    );
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        statements,
+        [
+            "ALTER TABLE \"users\" ADD COLUMN \"email\" TEXT",
+            "ALTER TABLE \"users\" DROP COLUMN \"age\"",
+            "ALTER TABLE \"users\" ALTER COLUMN \"name\" SET NOT NULL",
+        ]
+    );
+    #[cfg(feature = "mysql")]
+    assert_eq!(
+        statements,
+        [
+            "ALTER TABLE `users` ADD COLUMN `email` VARCHAR(255)",
+            "ALTER TABLE `users` DROP COLUMN `age`",
+            "ALTER TABLE `users` MODIFY COLUMN `name` VARCHAR(255) NOT NULL",
+        ]
+    );
+}
+
+#[test]
+fn test_compare_type() {
+    let expected = User::describe();
+
+    #[cfg(feature = "sqlite")]
+    let actual = [
+        column("id", "INTEGER", false),
+        column("name", "BLOB", false),
+        column("email", "TEXT", true),
+    ];
+    #[cfg(feature = "postgres")]
+    let actual = [
+        column("id", "int8", false),
+        column("name", "bytea", false),
+        column("email", "text", true),
+    ];
+    #[cfg(feature = "mysql")]
+    let actual = [
+        column("id", "bigint", false),
+        column("name", "blob", false),
+        column("email", "text", true),
+    ];
+
+    let diff = compare(User::TABLE, &expected, &actual);
+    assert!(diff.missing.is_empty());
+    assert!(diff.extra.is_empty());
+    assert_eq!(diff.mistyped.len(), 1);
+    assert_eq!(diff.mistyped[0].1.name, "name");
+
+    let statements = diff.alter_statements();
+    #[cfg(feature = "sqlite")]
+    assert!(statements.is_empty());
+    #[cfg(feature = "postgres")]
+    assert_eq!(
+        statements,
+        ["ALTER TABLE \"users\" ALTER COLUMN \"name\" TYPE TEXT, ALTER COLUMN \"name\" SET NOT NULL"]
+    );
+    #[cfg(feature = "mysql")]
+    assert_eq!(
+        statements,
+        ["ALTER TABLE `users` MODIFY COLUMN `name` VARCHAR(255) NOT NULL"]
+    );
+}
+
+#[cfg(feature = "sqlite")]
+#[sqlx::test]
+async fn test_diff() {
+    use sqlx::{Connection, Executor, SqliteConnection};
+
+    let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+    conn.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, age INT NOT NULL DEFAULT 0)")
+        .await
+        .unwrap();
+
+    let diff = sorm::schema::diff::<User>(&mut conn).await.unwrap();
+    let missing: Vec<_> = diff.missing.iter().map(|v| v.name.as_str()).collect();
+    assert_eq!(missing, ["name", "email"]);
+    assert_eq!(diff.extra.len(), 1);
+    assert_eq!(diff.extra[0].name, "age");
+    assert!(diff.mistyped.is_empty());
+
+    // The NOT NULL `name` column is added as nullable, which the existing rows require.
+    conn.execute("INSERT INTO users (id) VALUES (1)").await.unwrap();
+    let statements = diff.alter_statements();
+    assert_eq!(
+        statements,
+        [
+            "ALTER TABLE `users` ADD COLUMN `name` TEXT",
+            "ALTER TABLE `users` ADD COLUMN `email` TEXT",
+            "ALTER TABLE `users` DROP COLUMN `age`",
+        ]
+    );
+    for sql in &statements {
+        conn.execute(sql.as_str()).await.unwrap();
+    }
+
+    let diff = sorm::schema::diff::<User>(&mut conn).await.unwrap();
+    assert!(diff.missing.is_empty());
+    assert!(diff.extra.is_empty());
+    assert_eq!(diff.mistyped.len(), 1);
+    assert_eq!(diff.mistyped[0].0.name, "name");
+    assert!(diff.mistyped[0].1.nullable);
+
+    #[sorm(table = "posts")]
+    struct Post {
+        id: i64,
+    }
+
+    assert!(matches!(
+        sorm::schema::diff::<Post>(&mut conn).await,
+        Err(sorm::Error::TableNotFound(table)) if table == "posts"
+    ));
+}
@@ -0,0 +1,121 @@
+#[cfg(feature = "sqlite")]
+#[sqlx::test]
+async fn test_transaction() {
+    use std::panic::AssertUnwindSafe;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use futures_util::FutureExt;
+    use sorm::{transaction, transaction_with, Isolation, TransactionOptions};
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+    use sqlx::{Connection, Executor, SqliteConnection};
+
+    let path = std::env::temp_dir().join(format!("sorm-transaction-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    // Fails with SQLITE_BUSY at once when the database is locked.
+    let connect = SqliteConnectOptions::new()
+        .filename(&path)
+        .create_if_missing(true)
+        .busy_timeout(Duration::ZERO);
+    let pool = SqlitePoolOptions::new()
+        .connect_with(connect.clone())
+        .await
+        .unwrap();
+    pool.execute("CREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT)")
+        .await
+        .unwrap();
+
+    let count = || async {
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM posts")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        count
+    };
+
+    let id = transaction(&pool, |tx| {
+        Box::pin(async move {
+            let result = sqlx::query("INSERT INTO posts (title) VALUES ('foo')")
+                .execute(&mut **tx)
+                .await?;
+            Ok(result.last_insert_rowid())
+        })
+    })
+    .await
+    .unwrap();
+    assert_eq!(id, 1);
+    assert_eq!(count().await, 1);
+
+    let result: sorm::Result<()> = transaction(&pool, |tx| {
+        Box::pin(async move {
+            sqlx::query("INSERT INTO posts (title) VALUES ('bar')")
+                .execute(&mut **tx)
+                .await?;
+            Err(sorm::Error::NoWhereClause)
+        })
+    })
+    .await;
+    assert!(matches!(result, Err(sorm::Error::NoWhereClause)));
+    assert_eq!(count().await, 1);
+
+    let result = AssertUnwindSafe(transaction::<(), _>(&pool, |tx| {
+        Box::pin(async move {
+            sqlx::query("INSERT INTO posts (title) VALUES ('bar')")
+                .execute(&mut **tx)
+                .await?;
+            panic!("failed")
+        })
+    }))
+    .catch_unwind()
+    .await;
+    assert!(result.is_err());
+    assert_eq!(count().await, 1);
+
+    // Another connection holds the write lock until the first attempt fails.
+    let mut blocker = SqliteConnection::connect_with(&connect).await.unwrap();
+    blocker.execute("BEGIN IMMEDIATE").await.unwrap();
+    let blocker = Arc::new(Mutex::new(Some(blocker)));
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let options = TransactionOptions::new()
+        .isolation(Isolation::Serializable)
+        .retry(3, Duration::from_millis(1));
+    transaction_with(&pool, &options, |tx| {
+        let blocker = blocker.clone();
+        let attempts = attempts.clone();
+        Box::pin(async move {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            let result = sqlx::query("INSERT INTO posts (title) VALUES ('baz')")
+                .execute(&mut **tx)
+                .await;
+            let blocker = blocker.lock().unwrap().take();
+            if let Some(mut blocker) = blocker {
+                blocker.execute("ROLLBACK").await.unwrap();
+            }
+            result?;
+            Ok(())
+        })
+    })
+    .await
+    .unwrap();
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    assert_eq!(count().await, 2);
+
+    // Not retried by default.
+    let mut blocker = SqliteConnection::connect_with(&connect).await.unwrap();
+    blocker.execute("BEGIN IMMEDIATE").await.unwrap();
+    let result = transaction(&pool, |tx| {
+        Box::pin(async move {
+            sqlx::query("INSERT INTO posts (title) VALUES ('baz')")
+                .execute(&mut **tx)
+                .await?;
+            Ok(())
+        })
+    })
+    .await;
+    assert!(matches!(result, Err(sorm::Error::Sqlx(sqlx::Error::Database(_)))));
+    blocker.execute("ROLLBACK").await.unwrap();
+
+    pool.close().await;
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,211 @@
+//! Opaque cursors for keyset pagination.
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use sqlx::error::BoxDynError;
+use sqlx::{Arguments, Decode, Row, Type, ValueRef};
+
+use crate::{Database, Error};
+
+/// A key value of a row.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Int(i64),
+    #[cfg(feature = "mysql")]
+    UInt(u64),
+    Float(f64),
+    Bool(bool),
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl Value {
+    /// Reads the value of `column` from the row, trying the supported types in turn.
+    ///
+    /// Only integers, floats, booleans, text and binary columns are supported, other types such
+    /// as timestamps, numerics and UUIDs are rejected with [`Error::UnsupportedCursorColumn`].
+    /// NULL values cannot be compared in the cursor condition and are rejected with
+    /// [`Error::NullCursorColumn`].
+    pub(crate) fn read(row: &crate::Row, column: &str) -> crate::Result<Self> {
+        if row.try_get_raw(column)?.is_null() {
+            return Err(Error::NullCursorColumn(column.to_owned()));
+        }
+
+        macro_rules! try_get {
+            ($($ty:ty => $variant:expr),* $(,)?) => {
+                $(
+                    if let Some(v) = try_get::<$ty>(row, column)? {
+                        return Ok($variant(v));
+                    }
+                )*
+            };
+        }
+
+        try_get!(
+            i64 => Value::Int,
+            i32 => |v| Value::Int(v as i64),
+            i16 => |v| Value::Int(v as i64),
+            i8 => |v| Value::Int(v as i64),
+        );
+        #[cfg(feature = "mysql")]
+        try_get!(
+            u64 => Value::UInt,
+            u32 => |v| Value::Int(v as i64),
+            u16 => |v| Value::Int(v as i64),
+            u8 => |v| Value::Int(v as i64),
+        );
+        try_get!(
+            bool => Value::Bool,
+            f64 => Value::Float,
+            f32 => |v| Value::Float(v as f64),
+            String => Value::Text,
+            Vec<u8> => Value::Bytes,
+        );
+
+        let name = row.try_get_raw(column)?.type_info().to_string();
+        Err(Error::UnsupportedCursorColumn(column.to_owned(), name))
+    }
+
+    #[cfg(feature = "sqlite")]
+    pub(crate) fn add(&self, arguments: &mut crate::Arguments<'_>) -> Result<(), BoxDynError> {
+        self.add_to(arguments)
+    }
+
+    #[cfg(any(feature = "mysql", feature = "postgres"))]
+    pub(crate) fn add(&self, arguments: &mut crate::Arguments) -> Result<(), BoxDynError> {
+        self.add_to(arguments)
+    }
+
+    fn add_to<'q>(
+        &self,
+        arguments: &mut impl Arguments<'q, Database = Database>,
+    ) -> Result<(), BoxDynError> {
+        match self {
+            Value::Int(v) => arguments.add(*v),
+            #[cfg(feature = "mysql")]
+            Value::UInt(v) => arguments.add(*v),
+            Value::Float(v) => arguments.add(*v),
+            Value::Bool(v) => arguments.add(*v),
+            Value::Text(v) => arguments.add(v.clone()),
+            Value::Bytes(v) => arguments.add(v.clone()),
+        }
+    }
+
+    #[cfg(feature = "test")]
+    pub(crate) fn debug(&self) -> String {
+        match self {
+            Value::Int(v) => format!("{:?}", v),
+            #[cfg(feature = "mysql")]
+            Value::UInt(v) => format!("{:?}", v),
+            Value::Float(v) => format!("{:?}", v),
+            Value::Bool(v) => format!("{:?}", v),
+            Value::Text(v) => format!("{:?}", v),
+            Value::Bytes(v) => format!("{:?}", v),
+        }
+    }
+}
+
+fn try_get<'r, T>(row: &'r crate::Row, column: &str) -> crate::Result<Option<T>>
+where
+    T: Decode<'r, Database> + Type<Database>,
+{
+    match row.try_get(column) {
+        Ok(v) => Ok(Some(v)),
+        Err(sqlx::Error::ColumnDecode { .. }) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// A position in an ordered result set.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Cursor {
+    /// `true` to fetch the rows before the position, `false` for the rows after it.
+    pub(crate) before: bool,
+    pub(crate) values: Vec<Value>,
+}
+
+impl Cursor {
+    /// Encodes the cursor to an URL safe string.
+    pub(crate) fn encode(&self) -> String {
+        let mut buf = Vec::with_capacity(16 * self.values.len() + 1);
+        buf.push(if self.before { b'p' } else { b'n' });
+        for v in &self.values {
+            match v {
+                Value::Int(v) => encode_number(&mut buf, b'i', v),
+                #[cfg(feature = "mysql")]
+                Value::UInt(v) => encode_number(&mut buf, b'u', v),
+                Value::Float(v) => encode_number(&mut buf, b'f', &v.to_bits()),
+                Value::Bool(v) => buf.extend_from_slice(if *v { b"t" } else { b"F" }),
+                Value::Text(v) => encode_bytes(&mut buf, b's', v.as_bytes()),
+                Value::Bytes(v) => encode_bytes(&mut buf, b'x', v),
+            }
+        }
+        URL_SAFE_NO_PAD.encode(buf)
+    }
+
+    /// Decodes a cursor returned by [`Cursor::encode`].
+    pub(crate) fn decode(s: &str) -> crate::Result<Self> {
+        let buf = URL_SAFE_NO_PAD
+            .decode(s)
+            .map_err(|_| Error::InvalidCursor)?;
+        let (before, mut rest) = match buf.split_first() {
+            Some((b'p', rest)) => (true, rest),
+            Some((b'n', rest)) => (false, rest),
+            _ => return Err(Error::InvalidCursor),
+        };
+
+        let mut values = Vec::new();
+        while let Some((&tag, tail)) = rest.split_first() {
+            rest = tail;
+            let value = match tag {
+                b't' => Value::Bool(true),
+                b'F' => Value::Bool(false),
+                b'i' => Value::Int(decode_number(&mut rest)?),
+                #[cfg(feature = "mysql")]
+                b'u' => Value::UInt(decode_number(&mut rest)?),
+                b'f' => Value::Float(f64::from_bits(decode_number(&mut rest)?)),
+                b's' => Value::Text(
+                    String::from_utf8(decode_bytes(&mut rest)?)
+                        .map_err(|_| Error::InvalidCursor)?,
+                ),
+                b'x' => Value::Bytes(decode_bytes(&mut rest)?),
+                _ => return Err(Error::InvalidCursor),
+            };
+            values.push(value);
+        }
+        Ok(Self { before, values })
+    }
+}
+
+fn encode_number(buf: &mut Vec<u8>, tag: u8, v: &impl ToString) {
+    buf.push(tag);
+    buf.extend_from_slice(v.to_string().as_bytes());
+    buf.push(b';');
+}
+
+fn decode_number<T: std::str::FromStr>(rest: &mut &[u8]) -> crate::Result<T> {
+    let end = rest
+        .iter()
+        .position(|v| *v == b';')
+        .ok_or(Error::InvalidCursor)?;
+    let v = std::str::from_utf8(&rest[..end])
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .ok_or(Error::InvalidCursor)?;
+    *rest = &rest[end + 1..];
+    Ok(v)
+}
+
+fn encode_bytes(buf: &mut Vec<u8>, tag: u8, v: &[u8]) {
+    encode_number(buf, tag, &v.len());
+    buf.extend_from_slice(v);
+}
+
+fn decode_bytes(rest: &mut &[u8]) -> crate::Result<Vec<u8>> {
+    let len: usize = decode_number(rest)?;
+    if rest.len() < len {
+        return Err(Error::InvalidCursor);
+    }
+    let (v, tail) = rest.split_at(len);
+    *rest = tail;
+    Ok(v.to_vec())
+}
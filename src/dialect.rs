@@ -0,0 +1,48 @@
+//! Differences between the SQL dialects of the backends.
+
+/// The SQL dialect of a backend.
+///
+/// Only the dialect of the enabled backend is constructed.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Dialect {
+    Sqlite,
+    Mysql,
+    Postgres,
+}
+
+impl Dialect {
+    /// The dialect of the enabled backend.
+    #[cfg(feature = "sqlite")]
+    pub(crate) const CURRENT: Dialect = Dialect::Sqlite;
+    /// The dialect of the enabled backend.
+    #[cfg(feature = "mysql")]
+    pub(crate) const CURRENT: Dialect = Dialect::Mysql;
+    /// The dialect of the enabled backend.
+    #[cfg(feature = "postgres")]
+    pub(crate) const CURRENT: Dialect = Dialect::Postgres;
+
+    /// Returns `true` if the dialect supports row locking clauses like `FOR UPDATE`.
+    ///
+    /// SQLite has no row locks, a write transaction locks the whole database.
+    #[inline]
+    pub(crate) const fn supports_row_lock(self) -> bool {
+        !matches!(self, Dialect::Sqlite)
+    }
+
+    /// Returns `true` if the dialect supports `NULLS FIRST` and `NULLS LAST` in ORDER BY.
+    #[inline]
+    pub(crate) const fn supports_nulls_order(self) -> bool {
+        !matches!(self, Dialect::Mysql)
+    }
+
+    /// Returns the maximum number of parameters of a statement.
+    #[inline]
+    pub(crate) const fn max_params(self) -> usize {
+        match self {
+            // SQLITE_MAX_VARIABLE_NUMBER, since SQLite 3.32.
+            Dialect::Sqlite => 32766,
+            Dialect::Mysql | Dialect::Postgres => 65535,
+        }
+    }
+}
@@ -20,6 +20,46 @@ pub enum Error {
     #[error("no where clause")]
     NoWhereClause,
 
+    /// Raised when a pagination cursor is malformed or does not match the ordering of the query.
+    #[error("invalid cursor")]
+    InvalidCursor,
+
+    /// Raised when a column used as a pagination key has a type cursors cannot hold.
+    #[error("unsupported type {1} of cursor column {0}")]
+    UnsupportedCursorColumn(String, String),
+
+    /// Raised when a column used as a pagination key is NULL in a row of the page.
+    #[error("cursor column {0} is NULL")]
+    NullCursorColumn(String),
+
+    /// Raised when cursor pagination is used on a query without ORDER BY columns.
+    #[error("no order by columns")]
+    NoOrderBy,
+
+    /// Raised when filter parameters have an unknown field, operator or sort column.
+    #[error("invalid filter: {0}")]
+    InvalidFilter(String),
+
+    /// Raised when an ordering parsed by `order_by_checked` has a column that is not allowed.
+    #[error("invalid order by: {0}")]
+    InvalidOrderBy(String),
+
+    /// Raised when a column name is not one of the columns of the model.
+    #[error("unknown column {0}")]
+    UnknownColumn(String),
+
+    /// Raised when executing an INSERT query whose rows do not set the same columns.
+    #[error("invalid insert: {0}")]
+    InvalidInsert(&'static str),
+
+    /// Raised when executing an UPDATE or DELETE query with JOIN clauses.
+    #[error("joins are not supported by update and delete queries")]
+    UnsupportedJoin,
+
+    /// Raised when introspecting a table that does not exist.
+    #[error("table {0} not found")]
+    TableNotFound(String),
+
     /// Errors from sqlx.
     #[error(transparent)]
     Sqlx(#[from] sqlx::Error),
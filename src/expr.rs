@@ -0,0 +1,257 @@
+//! Typed column expressions.
+//!
+//! The `#[sorm]` macro generates a `col()` function that returns the typed columns of a model.
+//! Each column builds [`Predicate`]s that only accept values of the field's type.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use sorm::sorm;
+//! # use sorm::model::Model;
+//! # use sorm::query::Query;
+//! #[sorm(table = "users")]
+//! struct User {
+//!     id: i64,
+//!     name: String,
+//!     age: i32,
+//! }
+//!
+//! let name = "foo".to_string();
+//! let (min, max) = (18, 30);
+//! let ids = [1, 2, 3];
+//! let col = User::col();
+//! let predicate = col
+//!     .name
+//!     .eq(&name)
+//!     .and(col.age.between(&min, &max))
+//!     .or(col.id.in_list(&ids));
+//! let mut query = Query::table(User::TABLE);
+//! query.r#where(&predicate);
+//! // SELECT * FROM `users` WHERE (`name`=? AND `age` BETWEEN ? AND ? OR `id` IN (?,?,?))
+//! ```
+use std::marker::PhantomData;
+
+use crate::{concat_ident, Clause, Param};
+
+/// A typed column of a model.
+pub struct Column<T> {
+    name: &'static str,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Column<T> {
+    #[doc(hidden)]
+    #[inline]
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the column name.
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Builds a `column IS NULL` predicate.
+    #[inline]
+    pub fn is_null<'q>(&self) -> Predicate<'q> {
+        self.unary(" IS NULL")
+    }
+
+    /// Builds a `column IS NOT NULL` predicate.
+    #[inline]
+    pub fn is_not_null<'q>(&self) -> Predicate<'q> {
+        self.unary(" IS NOT NULL")
+    }
+
+    fn unary<'q>(&self, op: &str) -> Predicate<'q> {
+        let mut expr = String::with_capacity(self.name.len() + op.len() + 2);
+        concat_ident(&mut expr, self.name);
+        expr.push_str(op);
+        Predicate::atom(expr, Vec::new())
+    }
+}
+
+impl<'q, T: Param<'q> + Sync + 'q> Column<T> {
+    /// Builds a `column=value` predicate, or `column IS NULL` if the value is `NULL`.
+    #[inline]
+    pub fn eq(&self, value: &'q T) -> Predicate<'q> {
+        match value.is_null() {
+            true => self.is_null(),
+            false => self.binary("=", value),
+        }
+    }
+
+    /// Builds a `column<>value` predicate, or `column IS NOT NULL` if the value is `NULL`.
+    #[inline]
+    pub fn ne(&self, value: &'q T) -> Predicate<'q> {
+        match value.is_null() {
+            true => self.is_not_null(),
+            false => self.binary("<>", value),
+        }
+    }
+
+    /// Builds a `column>value` predicate.
+    #[inline]
+    pub fn gt(&self, value: &'q T) -> Predicate<'q> {
+        self.binary(">", value)
+    }
+
+    /// Builds a `column>=value` predicate.
+    #[inline]
+    pub fn ge(&self, value: &'q T) -> Predicate<'q> {
+        self.binary(">=", value)
+    }
+
+    /// Builds a `column<value` predicate.
+    #[inline]
+    pub fn lt(&self, value: &'q T) -> Predicate<'q> {
+        self.binary("<", value)
+    }
+
+    /// Builds a `column<=value` predicate.
+    #[inline]
+    pub fn le(&self, value: &'q T) -> Predicate<'q> {
+        self.binary("<=", value)
+    }
+
+    /// Builds a `column LIKE pattern` predicate.
+    #[inline]
+    pub fn like(&self, pattern: &'q T) -> Predicate<'q> {
+        self.binary(" LIKE ", pattern)
+    }
+
+    /// Builds a `column BETWEEN low AND high` predicate.
+    pub fn between(&self, low: &'q T, high: &'q T) -> Predicate<'q> {
+        let mut expr = String::with_capacity(self.name.len() + 20);
+        concat_ident(&mut expr, self.name);
+        expr.push_str(" BETWEEN ? AND ?");
+        Predicate::atom(expr, vec![low, high])
+    }
+
+    /// Builds a `column IN (values)` predicate.
+    ///
+    /// An empty list builds a predicate that is always false.
+    #[inline]
+    pub fn in_list(&self, values: &'q [T]) -> Predicate<'q> {
+        self.list(" IN ", values, "1=0")
+    }
+
+    /// Builds a `column NOT IN (values)` predicate.
+    ///
+    /// An empty list builds a predicate that is always true.
+    #[inline]
+    pub fn not_in_list(&self, values: &'q [T]) -> Predicate<'q> {
+        self.list(" NOT IN ", values, "1=1")
+    }
+
+    fn binary(&self, op: &str, value: &'q T) -> Predicate<'q> {
+        let mut expr = String::with_capacity(self.name.len() + op.len() + 3);
+        concat_ident(&mut expr, self.name);
+        expr.push_str(op);
+        expr.push('?');
+        Predicate::atom(expr, vec![value])
+    }
+
+    fn list(&self, op: &str, values: &'q [T], empty: &str) -> Predicate<'q> {
+        if values.is_empty() {
+            return Predicate::atom(empty.to_string(), Vec::new());
+        }
+
+        let mut expr = String::with_capacity(self.name.len() + op.len() + values.len() * 2 + 3);
+        concat_ident(&mut expr, self.name);
+        expr.push_str(op);
+        expr.push('(');
+        let mut params = Vec::with_capacity(values.len());
+        for v in values {
+            expr.push_str("?,");
+            params.push(v as &(dyn Param<'q> + Sync));
+        }
+        expr.pop();
+        expr.push(')');
+        Predicate::atom(expr, params)
+    }
+}
+
+enum Kind {
+    Atom,
+    And,
+    Or,
+}
+
+/// A composable condition built from typed columns.
+///
+/// Predicates combined with [`Predicate::or`] are parenthesized, so a predicate can be
+/// passed to [`crate::query::Query::r#where`] and friends as is.
+pub struct Predicate<'q> {
+    expr: String,
+    params: Vec<&'q (dyn Param<'q> + Sync)>,
+    kind: Kind,
+}
+
+impl<'q> Predicate<'q> {
+    fn atom(expr: String, params: Vec<&'q (dyn Param<'q> + Sync)>) -> Self {
+        Self {
+            expr,
+            params,
+            kind: Kind::Atom,
+        }
+    }
+
+    /// Combines two predicates with the AND operator.
+    pub fn and(mut self, other: Predicate<'q>) -> Self {
+        self.expr.reserve(other.expr.len() + 5);
+        self.expr.push_str(" AND ");
+        self.expr.push_str(&other.expr);
+        self.params.extend(other.params);
+        self.kind = Kind::And;
+        self
+    }
+
+    /// Combines two predicates with the OR operator.
+    pub fn or(mut self, other: Predicate<'q>) -> Self {
+        let other_expr = match other.kind {
+            Kind::Or => &other.expr[1..other.expr.len() - 1],
+            _ => &other.expr,
+        };
+        match self.kind {
+            Kind::Or => {
+                self.expr.pop();
+            }
+            _ => self.expr.insert(0, '('),
+        }
+        self.expr.reserve(other_expr.len() + 5);
+        self.expr.push_str(" OR ");
+        self.expr.push_str(other_expr);
+        self.expr.push(')');
+        self.params.extend(other.params);
+        self.kind = Kind::Or;
+        self
+    }
+
+    /// Negates the predicate.
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(mut self) -> Self {
+        self.expr = match self.kind {
+            Kind::Or => format!("NOT {}", self.expr),
+            _ => format!("NOT ({})", self.expr),
+        };
+        self.kind = Kind::Atom;
+        self
+    }
+}
+
+impl<'q> Clause<'q> for &'q Predicate<'q> {
+    #[inline]
+    fn expr(&self) -> &'q str {
+        &self.expr
+    }
+
+    #[inline]
+    fn params(&self) -> &'q [&'q (dyn Param<'q> + Sync)] {
+        &self.params
+    }
+}
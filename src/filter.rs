@@ -0,0 +1,392 @@
+//! Dynamic filters parsed from HTTP query parameters.
+//!
+//! A [`Filter`] whitelists the columns of a model that can be filtered, with their operators,
+//! and the columns that can be sorted. Parsing parameters like
+//! `?name__like=foo%&age__gte=18&sort=-created_at` against it returns [`Criteria`], whose values
+//! are bound as parameters, never written in the SQL.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use sorm::sorm;
+//! # use sorm::filter::{Filter, Op};
+//! # use sorm::model::Model;
+//! #[sorm(table = "users")]
+//! struct User {
+//!     id: i64,
+//!     name: String,
+//!     age: i32,
+//!     created_at: i64,
+//! }
+//!
+//! let filter = Filter::<User>::new()
+//!     .allow("name", &[Op::Eq, Op::Like])?
+//!     .allow("age", &[Op::Eq, Op::Gte, Op::Lte])?
+//!     .sortable(&["id", "created_at"])?
+//!     .ignore(&["page"]);
+//! let params = [("name__like", "foo%"), ("age__gte", "18"), ("sort", "-created_at"), ("page", "2")];
+//! let criteria = filter.parse(params).unwrap();
+//! let mut query = User::query();
+//! criteria.apply(&mut query);
+//! // SELECT `id`,`name`,`age`,`created_at` FROM `users` WHERE (`name` LIKE ?) AND (`age`>=?) ORDER BY `created_at` DESC
+//!
+//! assert!(filter.parse([("password", "foo")]).is_err());
+//! assert!(filter.parse([("age__like", "1%")]).is_err());
+//! # Ok::<(), sorm::Error>(())
+//! ```
+use std::fmt::{self, Display, Formatter};
+use std::marker::PhantomData;
+
+use crate::model::Model;
+use crate::query::{parse_sort, Query};
+use crate::{concat_ident, Error, Param};
+
+/// An operator of a filter, written as the `__` suffix of the field, e.g. `age__gte`.
+///
+/// A field without suffix uses [`Op::Eq`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// `=`, without suffix or with `__eq`.
+    Eq,
+    /// `<>`, with `__ne`.
+    Ne,
+    /// `<`, with `__lt`.
+    Lt,
+    /// `<=`, with `__lte`.
+    Lte,
+    /// `>`, with `__gt`.
+    Gt,
+    /// `>=`, with `__gte`.
+    Gte,
+    /// `LIKE`, with `__like`. The value is the pattern, with its `%` and `_` wildcards.
+    ///
+    /// It is only allowed on text columns.
+    Like,
+    /// `IN`, with `__in`. The value is a comma separated list.
+    In,
+    /// `IS NULL` if the value is `true`, `IS NOT NULL` if it is `false`, with `__isnull`.
+    IsNull,
+}
+
+impl Op {
+    const ALL: [Op; 9] = [
+        Op::Eq,
+        Op::Ne,
+        Op::Lt,
+        Op::Lte,
+        Op::Gt,
+        Op::Gte,
+        Op::Like,
+        Op::In,
+        Op::IsNull,
+    ];
+
+    /// Returns the suffix of the operator.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Op::Eq => "eq",
+            Op::Ne => "ne",
+            Op::Lt => "lt",
+            Op::Lte => "lte",
+            Op::Gt => "gt",
+            Op::Gte => "gte",
+            Op::Like => "like",
+            Op::In => "in",
+            Op::IsNull => "isnull",
+        }
+    }
+
+    /// Returns the operator of a suffix.
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|v| v.suffix() == suffix)
+    }
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.suffix())
+    }
+}
+
+/// The filterable and sortable columns of a model.
+pub struct Filter<T: Model> {
+    columns: Vec<(&'static str, &'static [Op])>,
+    sortable: Vec<&'static str>,
+    ignored: Vec<&'static str>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Model> Filter<T> {
+    /// The parameter holding the comma separated sort columns, each prefixed with `-` for the
+    /// descending order.
+    pub const SORT: &'static str = "sort";
+
+    /// Constructs a new filter, allowing no column.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            columns: Vec::new(),
+            sortable: Vec::new(),
+            ignored: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Allows filtering `column` with the operators `ops`.
+    ///
+    /// Returns [`Error::UnknownColumn`] if `column` is not one of [`Model::COLUMNS`], and
+    /// [`Error::InvalidFilter`] if `ops` has [`Op::Like`] and the column is not a text column.
+    pub fn allow(mut self, column: &'static str, ops: &'static [Op]) -> crate::Result<Self> {
+        check_column::<T>(column)?;
+        if ops.contains(&Op::Like) && !is_text::<T>(column) {
+            return Err(Error::InvalidFilter(format!(
+                "operator `like` is not allowed on the non-text column `{}`",
+                column
+            )));
+        }
+        self.columns.push((column, ops));
+        Ok(self)
+    }
+
+    /// Allows sorting by `columns`.
+    ///
+    /// Returns [`Error::UnknownColumn`] if a column is not one of [`Model::COLUMNS`].
+    pub fn sortable(mut self, columns: &[&'static str]) -> crate::Result<Self> {
+        for column in columns {
+            check_column::<T>(column)?;
+        }
+        self.sortable.extend_from_slice(columns);
+        Ok(self)
+    }
+
+    /// Ignores the parameters `keys`, like the page of a paginated list.
+    pub fn ignore(mut self, keys: &[&'static str]) -> Self {
+        self.ignored.extend_from_slice(keys);
+        self
+    }
+
+    /// Parses the parameters into criteria.
+    ///
+    /// Returns [`Error::InvalidFilter`] for unknown fields, operators and sort columns, and for
+    /// operators that are not allowed on their column. Values of integer, floating point and
+    /// boolean columns are checked against the column type, and booleans accept `true`, `false`,
+    /// `1` and `0`.
+    pub fn parse<K, V>(&self, params: impl IntoIterator<Item = (K, V)>) -> crate::Result<Criteria>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let describe = T::describe();
+        let mut criteria = Criteria::default();
+        for (key, value) in params {
+            let (key, value) = (key.as_ref(), value.as_ref());
+            if self.ignored.contains(&key) {
+                continue;
+            }
+            if key == Self::SORT {
+                self.parse_sort(&mut criteria, value)?;
+                continue;
+            }
+
+            let (column, ops, op) = self.find(key)?;
+            if !ops.contains(&op) {
+                return Err(Error::InvalidFilter(format!(
+                    "operator `{}` is not allowed on `{}`",
+                    op, column
+                )));
+            }
+
+            let type_name = describe
+                .iter()
+                .find(|v| v.name == column)
+                .map(|v| v.type_name.as_str());
+
+            // PostgreSQL doesn't convert the text parameters to the type of the column.
+            #[cfg(feature = "postgres")]
+            let placeholder = match type_name {
+                Some(v) => format!("CAST(? AS {})", v),
+                None => "?".to_string(),
+            };
+            #[cfg(feature = "postgres")]
+            let placeholder = placeholder.as_str();
+            #[cfg(not(feature = "postgres"))]
+            let placeholder = "?";
+
+            let mut expr = String::with_capacity(column.len() + 16);
+            concat_ident(&mut expr, column);
+            let values = match op {
+                Op::Like => {
+                    expr.push_str(" LIKE ?");
+                    vec![value.to_string()]
+                }
+                Op::In => {
+                    let values = value
+                        .split(',')
+                        .map(|v| check_value(key, type_name, v))
+                        .collect::<crate::Result<Vec<_>>>()?;
+                    expr.push_str(" IN (");
+                    for _ in &values {
+                        expr.push_str(placeholder);
+                        expr.push(',');
+                    }
+                    expr.pop();
+                    expr.push(')');
+                    values
+                }
+                Op::IsNull => {
+                    match value {
+                        "true" => expr.push_str(" IS NULL"),
+                        "false" => expr.push_str(" IS NOT NULL"),
+                        _ => {
+                            return Err(Error::InvalidFilter(format!(
+                                "`{}` expects true or false",
+                                key
+                            )))
+                        }
+                    }
+                    Vec::new()
+                }
+                _ => {
+                    expr.push_str(match op {
+                        Op::Ne => "<>",
+                        Op::Lt => "<",
+                        Op::Lte => "<=",
+                        Op::Gt => ">",
+                        Op::Gte => ">=",
+                        _ => "=",
+                    });
+                    expr.push_str(placeholder);
+                    vec![check_value(key, type_name, value)?]
+                }
+            };
+            criteria.conditions.push((expr, values));
+        }
+        Ok(criteria)
+    }
+
+    /// Returns the column, its allowed operators and the operator of a field.
+    fn find(&self, key: &str) -> crate::Result<(&'static str, &'static [Op], Op)> {
+        if let Some((column, ops)) = self.column(key) {
+            return Ok((column, ops, Op::Eq));
+        }
+        if let Some((field, suffix)) = key.rsplit_once("__") {
+            if let Some((column, ops)) = self.column(field) {
+                let op = Op::from_suffix(suffix).ok_or_else(|| {
+                    Error::InvalidFilter(format!("unknown operator `{}` of `{}`", suffix, key))
+                })?;
+                return Ok((column, ops, op));
+            }
+        }
+        Err(Error::InvalidFilter(format!("unknown field `{}`", key)))
+    }
+
+    fn column(&self, name: &str) -> Option<(&'static str, &'static [Op])> {
+        self.columns.iter().find(|v| v.0 == name).copied()
+    }
+
+    fn parse_sort(&self, criteria: &mut Criteria, value: &str) -> crate::Result<()> {
+        let unknown = |v| Error::InvalidFilter(format!("unknown sort field `{}`", v));
+        for (name, desc, nulls) in parse_sort(value).map_err(unknown)? {
+            if nulls.is_some() {
+                return Err(Error::InvalidFilter(format!(
+                    "the position of the NULL values of `{}` cannot be set",
+                    name
+                )));
+            }
+            let column =
+                self.sortable.iter().find(|v| **v == name).ok_or_else(|| {
+                    Error::InvalidFilter(format!("unknown sort field `{}`", name))
+                })?;
+            criteria.order_by.push((column, desc));
+        }
+        Ok(())
+    }
+}
+
+impl<T: Model> Default for Filter<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn check_column<T: Model>(column: &str) -> crate::Result<()> {
+    match T::COLUMNS.contains(&column) {
+        true => Ok(()),
+        false => Err(Error::UnknownColumn(column.to_owned())),
+    }
+}
+
+/// Returns `true` if the type of `column` is a text type, like `TEXT` or `VARCHAR`.
+fn is_text<T: Model>(column: &str) -> bool {
+    T::describe()
+        .iter()
+        .find(|v| v.name == column)
+        .is_some_and(|v| {
+            let name = v.type_name.to_ascii_uppercase();
+            name.contains("TEXT") || name.contains("CHAR")
+        })
+}
+
+/// Checks that `value` can be converted to the column type `type_name` and returns the value
+/// to bind, with booleans written as `1` or `0`.
+fn check_value(key: &str, type_name: Option<&str>, value: &str) -> crate::Result<String> {
+    let type_name = type_name.unwrap_or_default().to_ascii_uppercase();
+    let (kind, valid) = match type_name.split([' ', '(']).next().unwrap_or_default() {
+        "BOOL" | "BOOLEAN" => match value {
+            "true" | "1" => return Ok("1".to_owned()),
+            "false" | "0" => return Ok("0".to_owned()),
+            _ => ("a boolean", false),
+        },
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "INTEGER" | "BIGINT" | "INT2" | "INT4"
+        | "INT8" => (
+            "an integer",
+            value.parse::<i64>().is_ok() || value.parse::<u64>().is_ok(),
+        ),
+        "REAL" | "FLOAT" | "DOUBLE" | "FLOAT4" | "FLOAT8" | "NUMERIC" | "DECIMAL" => {
+            ("a number", value.parse::<f64>().is_ok_and(f64::is_finite))
+        }
+        _ => ("", true),
+    };
+    match valid {
+        true => Ok(value.to_owned()),
+        false => Err(Error::InvalidFilter(format!(
+            "`{}` expects {}, got `{}`",
+            key, kind, value
+        ))),
+    }
+}
+
+/// The conditions and ordering parsed by [`Filter::parse`].
+#[derive(Debug, Clone, Default)]
+pub struct Criteria {
+    conditions: Vec<(String, Vec<String>)>,
+    order_by: Vec<(&'static str, bool)>,
+}
+
+impl Criteria {
+    /// Returns `true` if there are no conditions and no ordering.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty() && self.order_by.is_empty()
+    }
+
+    /// Adds the conditions to the WHERE clause of the query, and the ordering to its ORDER BY
+    /// clause.
+    pub fn apply<'q>(&'q self, query: &mut impl AsMut<Query<'q>>) {
+        let query = query.as_mut();
+        for (expr, values) in &self.conditions {
+            query.where_params(
+                expr,
+                values.iter().map(|v| v as &(dyn Param + Sync)).collect(),
+            );
+        }
+        for (column, desc) in &self.order_by {
+            if *desc {
+                query.order_by_desc(column);
+            } else {
+                query.order_by(column);
+            }
+        }
+    }
+}
@@ -17,6 +17,13 @@
 /// Parameters can be any type that implements [Param].
 /// The `#` before the parameter indicates that it's a vec or slice of parameters.
 ///
+/// On PostgreSQL, the `?` placeholders are numbered `$1`, `$2`, ... except in string literals,
+/// quoted identifiers and comments, and `??` is written as a literal `?`, e.g. for the JSONB
+/// operators `?`, `?|` and `?&`.
+///
+/// The `??` escape is PostgreSQL only: SQLite and MySQL receive the SQL as is, where `??` is two
+/// placeholders.
+///
 /// If a second argument is passed to clause!, it will be used to bind the params value,
 /// which is necessary in certain scenarios.
 ///
@@ -52,6 +59,9 @@
 ///    // ...
 /// }
 /// ```
+///
+/// A query that has to be returned, stored or moved to another task can be built with
+/// [`crate::query::QueryOwned`] and [`crate::query::ClauseOwned`] instead.
 pub use sorm_macros::clause;
 /// Generates model implementations.
 ///
@@ -163,6 +173,8 @@ pub use sorm_macros::clause;
 ///
 pub use sorm_macros::sorm;
 pub use sqlx;
+use sqlx::encode::IsNull;
+use sqlx::Encode;
 
 pub use error::{Error, Result};
 #[cfg(feature = "mysql")]
@@ -171,10 +183,17 @@ pub use mysql::*;
 pub use postgres::*;
 #[cfg(feature = "sqlite")]
 pub use sqlite::*;
+pub use transaction::{transaction, transaction_with, Isolation, TransactionOptions};
 
+mod cursor;
+mod dialect;
 mod error;
+pub mod expr;
+pub mod filter;
 pub mod model;
 pub mod query;
+pub mod schema;
+mod transaction;
 
 #[cfg(all(
     not(feature = "sqlite"),
@@ -198,8 +217,18 @@ mod sqlite {
     pub trait Param<'q> {
         fn add(&'q self, arguments: &mut Arguments<'q>) -> Result<(), BoxDynError>;
 
-        #[cfg(feature = "test")]
-        fn to_string(&self) -> String;
+        /// Returns a printable representation of the parameter, used by
+        /// [`crate::query::Query::to_sql`].
+        ///
+        /// It's the debug representation with the `test` feature, and the type name otherwise.
+        fn to_string(&self) -> String {
+            std::any::type_name_of_val(self).to_owned()
+        }
+
+        /// Returns `true` if the parameter is `NULL`, like `None`.
+        fn is_null(&self) -> bool {
+            false
+        }
     }
 
     #[cfg(not(feature = "test"))]
@@ -211,6 +240,10 @@ mod sqlite {
             use sqlx::Arguments;
             arguments.add(self)
         }
+
+        fn is_null(&self) -> bool {
+            crate::is_null(self)
+        }
     }
 
     #[cfg(feature = "test")]
@@ -226,6 +259,10 @@ mod sqlite {
         fn to_string(&self) -> String {
             format!("{:?}", self)
         }
+
+        fn is_null(&self) -> bool {
+            crate::is_null(self)
+        }
     }
 }
 
@@ -244,8 +281,18 @@ mod mysql {
     pub trait Param<'q> {
         fn add(&'q self, arguments: &mut Arguments) -> Result<(), BoxDynError>;
 
-        #[cfg(feature = "test")]
-        fn to_string(&self) -> String;
+        /// Returns a printable representation of the parameter, used by
+        /// [`crate::query::Query::to_sql`].
+        ///
+        /// It's the debug representation with the `test` feature, and the type name otherwise.
+        fn to_string(&self) -> String {
+            std::any::type_name_of_val(self).to_owned()
+        }
+
+        /// Returns `true` if the parameter is `NULL`, like `None`.
+        fn is_null(&self) -> bool {
+            false
+        }
     }
 
     #[cfg(not(feature = "test"))]
@@ -255,8 +302,11 @@ mod mysql {
     {
         fn add(&'q self, arguments: &mut Arguments) -> Result<(), BoxDynError> {
             use sqlx::Arguments;
-            arguments.add(self)?;
-            Ok(())
+            arguments.add(self)
+        }
+
+        fn is_null(&self) -> bool {
+            crate::is_null(self)
         }
     }
 
@@ -273,6 +323,10 @@ mod mysql {
         fn to_string(&self) -> String {
             format!("{:?}", self)
         }
+
+        fn is_null(&self) -> bool {
+            crate::is_null(self)
+        }
     }
 }
 
@@ -291,8 +345,18 @@ mod postgres {
     pub trait Param<'q> {
         fn add(&'q self, arguments: &mut Arguments) -> Result<(), BoxDynError>;
 
-        #[cfg(feature = "test")]
-        fn to_string(&self) -> String;
+        /// Returns a printable representation of the parameter, used by
+        /// [`crate::query::Query::to_sql`].
+        ///
+        /// It's the debug representation with the `test` feature, and the type name otherwise.
+        fn to_string(&self) -> String {
+            std::any::type_name_of_val(self).to_owned()
+        }
+
+        /// Returns `true` if the parameter is `NULL`, like `None`.
+        fn is_null(&self) -> bool {
+            false
+        }
     }
 
     #[cfg(not(feature = "test"))]
@@ -306,6 +370,10 @@ mod postgres {
             arguments.add(self)?;
             Ok(())
         }
+
+        fn is_null(&self) -> bool {
+            crate::is_null(self)
+        }
     }
 
     #[cfg(feature = "test")]
@@ -323,9 +391,19 @@ mod postgres {
         fn to_string(&self) -> String {
             format!("{:?}", self)
         }
+
+        fn is_null(&self) -> bool {
+            crate::is_null(self)
+        }
     }
 }
 
+/// Returns `true` if `value` is encoded as `NULL`.
+fn is_null<'q, T: Encode<'q, Database>>(value: &T) -> bool {
+    let mut buf: <Database as sqlx::Database>::ArgumentBuffer<'q> = Default::default();
+    matches!(value.encode_by_ref(&mut buf), Ok(IsNull::Yes))
+}
+
 /// Represents a sql clause.
 pub trait Clause<'q> {
     /// Returns the SQL expression corresponding to the clause.
@@ -371,19 +449,25 @@ impl<T: ?Sized> Lend for T {
 }
 
 #[cfg(feature = "postgres")]
-#[inline]
-fn concat_ident(s: &mut String, ident: &str) {
-    s.push_str("\"");
-    s.push_str(ident);
-    s.push_str("\"");
-}
+const QUOTE: char = '"';
 
 #[cfg(not(feature = "postgres"))]
-#[inline]
+const QUOTE: char = '`';
+
+/// Quotes an identifier, quoting each part of a qualified identifier like `users.id` separately.
 fn concat_ident(s: &mut String, ident: &str) {
-    s.push_str("`");
-    s.push_str(ident);
-    s.push_str("`");
+    for (i, part) in ident.split('.').enumerate() {
+        if i > 0 {
+            s.push('.');
+        }
+        if part == "*" {
+            s.push('*');
+        } else {
+            s.push(QUOTE);
+            s.push_str(part);
+            s.push(QUOTE);
+        }
+    }
 }
 
 fn concat_idents(s: &mut String, idents: &[&str]) {
@@ -1,12 +1,18 @@
 //! ORM model
 use std::borrow::Borrow;
+use std::future::Future;
 use std::marker::PhantomData;
 
+use crate::dialect::Dialect;
+#[cfg(feature = "postgres")]
+use crate::query::pg_replace_placeholder;
+use crate::query::{CursorPage, Number, Page, Sql, Table};
 use crate::{concat_ident, concat_idents, Clause, Database, Param, Row};
+use futures_core::stream::BoxStream;
 use log::debug;
 use sqlx::encode::IsNull;
 use sqlx::error::BoxDynError;
-use sqlx::{Arguments, Decode, Encode, Executor, FromRow, Type};
+use sqlx::{Acquire, Arguments, Decode, Encode, Executor, FromRow, Type};
 
 /// Represents a model.
 #[allow(async_fn_in_trait)]
@@ -31,6 +37,9 @@ pub trait Model: Default + for<'r> FromRow<'r, Row> + Send + Unpin + 'static {
     /// The columns of the table.
     const COLUMNS: &'static [&'static str];
 
+    /// Describes the columns of the table, in the same order as [`Model::COLUMNS`].
+    fn describe() -> Vec<crate::schema::Column>;
+
     /// Returns the primary.
     fn primary_key(&self) -> crate::Result<&Self::PrimaryKey>;
 
@@ -51,6 +60,9 @@ pub trait Model: Default + for<'r> FromRow<'r, Row> + Send + Unpin + 'static {
     /// It resets the change tracking mechanism, indicating that all fields are now considered unchanged.
     fn flush(&mut self);
 
+    /// Sets the fields to the columns of the row, leaving the fields without column untouched.
+    fn load_row(&mut self, row: &Row) -> sqlx::Result<()>;
+
     /// Sets default values for fields during creation.
     fn fill_create_default(&mut self) {}
 
@@ -188,6 +200,38 @@ pub trait Model: Default + for<'r> FromRow<'r, Row> + Send + Unpin + 'static {
         Ok(())
     }
 
+    /// Atomically increments `column` by `by` in the database, and sets the field to the new
+    /// value.
+    ///
+    /// Only the counter and the `update_time` fields are written, the other changed fields are
+    /// neither saved nor flushed. The new values are read with `RETURNING`, or in the same
+    /// transaction on MySQL, and set to the fields.
+    ///
+    /// Returns [`crate::Error::UnknownColumn`] if `column` is not one of [`Model::COLUMNS`].
+    #[inline]
+    async fn increment<'c>(
+        &mut self,
+        executor: impl Acquire<'c, Database = Database>,
+        column: &str,
+        by: impl for<'q> Param<'q> + Sync,
+    ) -> crate::Result<()> {
+        update_counter(self, executor, column, "+", &by).await
+    }
+
+    /// Atomically decrements `column` by `by` in the database, and sets the field to the new
+    /// value.
+    ///
+    /// See [`Model::increment`].
+    #[inline]
+    async fn decrement<'c>(
+        &mut self,
+        executor: impl Acquire<'c, Database = Database>,
+        column: &str,
+        by: impl for<'q> Param<'q> + Sync,
+    ) -> crate::Result<()> {
+        update_counter(self, executor, column, "-", &by).await
+    }
+
     /// Finds a model by its primary key or returns `Err`.
     async fn find<T>(
         executor: impl Executor<'_, Database = Database>,
@@ -285,6 +329,298 @@ pub trait Model: Default + for<'r> FromRow<'r, Row> + Send + Unpin + 'static {
             .rows_affected())
     }
 
+    /// Finds the models by their primary keys, in no particular order.
+    ///
+    /// The keys are split in chunks to stay under the parameter limit of the backend.
+    async fn find_many<'c, T>(
+        executor: impl Acquire<'c, Database = Database>,
+        primary_keys: &[T],
+    ) -> crate::Result<Vec<Self>>
+    where
+        Self::PrimaryKey: Borrow<T>,
+        for<'q> &'q T: Encode<'q, Database> + Type<Database>,
+        T: Sync,
+    {
+        let mut models = Vec::with_capacity(primary_keys.len());
+        if primary_keys.is_empty() {
+            return Ok(models);
+        }
+
+        let mut conn = executor.acquire().await?;
+        for chunk in primary_keys.chunks(Dialect::CURRENT.max_params()) {
+            let mut sql = String::with_capacity(32 + Self::COLUMNS.len() * 10 + chunk.len() * 2);
+            sql.push_str("SELECT ");
+            concat_idents(&mut sql, Self::COLUMNS);
+            sql.push_str(" FROM ");
+            concat_ident(&mut sql, Self::TABLE);
+            sql.push_str(" WHERE ");
+            concat_ident(&mut sql, Self::PRIMARY_KEY);
+            push_in(&mut sql, chunk.len());
+            #[cfg(feature = "postgres")]
+            let sql = pg_replace_placeholder(&sql);
+
+            debug!(target: "sorm", "{}", sql);
+            let mut query = sqlx::query_as(&sql);
+            for primary_key in chunk {
+                query = query.bind(primary_key);
+            }
+            models.extend(query.fetch_all(&mut *conn).await?);
+        }
+        Ok(models)
+    }
+
+    /// Deletes the models by their primary keys, returning the number of deleted rows.
+    ///
+    /// The keys are split in chunks to stay under the parameter limit of the backend, which are
+    /// deleted in a transaction.
+    async fn destroy_many<'c, T>(
+        executor: impl Acquire<'c, Database = Database>,
+        primary_keys: &[T],
+    ) -> crate::Result<u64>
+    where
+        Self::PrimaryKey: Borrow<T>,
+        for<'q> &'q T: Encode<'q, Database> + Type<Database>,
+        T: Sync,
+    {
+        if primary_keys.is_empty() {
+            return Ok(0);
+        }
+
+        let mut tx = executor.begin().await?;
+        let mut rows = 0;
+        for chunk in primary_keys.chunks(Dialect::CURRENT.max_params()) {
+            let mut sql = String::with_capacity(32 + chunk.len() * 2);
+            sql.push_str("DELETE FROM ");
+            concat_ident(&mut sql, Self::TABLE);
+            sql.push_str(" WHERE ");
+            concat_ident(&mut sql, Self::PRIMARY_KEY);
+            push_in(&mut sql, chunk.len());
+            #[cfg(feature = "postgres")]
+            let sql = pg_replace_placeholder(&sql);
+
+            debug!(target: "sorm", "{}", sql);
+            let mut query = sqlx::query(&sql);
+            for primary_key in chunk {
+                query = query.bind(primary_key);
+            }
+            rows += query.execute(&mut *tx).await?.rows_affected();
+        }
+        tx.commit().await?;
+        Ok(rows)
+    }
+
+    /// Updates the changed fields of the models in a transaction, returning the number of
+    /// updated rows.
+    ///
+    /// The `update_time` fields are filled, then the models are grouped by their changed
+    /// columns, and each group is updated with one statement, split in chunks to stay under the
+    /// parameter limit of the backend:
+    ///
+    /// ```sql
+    /// UPDATE `users` SET `name`=CASE `id` WHEN ? THEN ? WHEN ? THEN ? END WHERE `id` IN (?,?)
+    /// ```
+    ///
+    /// A changed primary key is not updated, and the models whose only change is their primary
+    /// key are left unflushed. If a model to update has no primary key, nothing is modified.
+    async fn update_many<'c>(
+        executor: impl Acquire<'c, Database = Database>,
+        models: &mut [Self],
+    ) -> crate::Result<u64> {
+        // The models are checked before any of them is modified.
+        let mut included = Vec::with_capacity(models.len());
+        for model in models.iter() {
+            let changed = model
+                .collect_changed()
+                .iter()
+                .any(|v| v.0 != Self::PRIMARY_KEY);
+            if changed {
+                model.primary_key()?;
+            }
+            included.push(changed);
+        }
+        for (model, _) in models.iter_mut().zip(&included).filter(|v| *v.1) {
+            model.fill_update_default();
+        }
+
+        let mut fields = Vec::with_capacity(models.len());
+        let mut groups: Vec<(Vec<&'static str>, Vec<usize>)> = Vec::new();
+        for (i, model) in models.iter().enumerate() {
+            if !included[i] {
+                fields.push(Vec::new());
+                continue;
+            }
+            let mut changed = model.collect_changed();
+            changed.retain(|v| v.0 != Self::PRIMARY_KEY);
+            let columns: Vec<_> = changed.iter().map(|v| v.0).collect();
+            fields.push(changed);
+            match groups.iter_mut().find(|v| v.0 == columns) {
+                Some(group) => group.1.push(i),
+                None => groups.push((columns, vec![i])),
+            }
+        }
+        if groups.is_empty() {
+            return Ok(0);
+        }
+
+        let mut tx = executor.begin().await?;
+        let mut rows = 0;
+        for (columns, indexes) in &groups {
+            let size = Dialect::CURRENT.max_params() / (2 * columns.len() + 1);
+            for chunk in indexes.chunks(size) {
+                let mut sql = String::with_capacity(32 + columns.len() * (20 + chunk.len() * 14));
+                let mut args = crate::Arguments::default();
+                let count = chunk.len() * (2 * columns.len() + 1);
+                args.reserve(count, count);
+                sql.push_str("UPDATE ");
+                concat_ident(&mut sql, Self::TABLE);
+                sql.push_str(" SET ");
+                for (n, column) in columns.iter().enumerate() {
+                    concat_ident(&mut sql, column);
+                    sql.push_str("=CASE ");
+                    concat_ident(&mut sql, Self::PRIMARY_KEY);
+                    for &i in chunk {
+                        sql.push_str(" WHEN ? THEN ?");
+                        (models[i].primary_key()? as &(dyn Param + Send)).add(&mut args)?;
+                        fields[i][n].1.add(&mut args)?;
+                    }
+                    sql.push_str(" END,");
+                }
+                sql.pop();
+                sql.push_str(" WHERE ");
+                concat_ident(&mut sql, Self::PRIMARY_KEY);
+                push_in(&mut sql, chunk.len());
+                for &i in chunk {
+                    (models[i].primary_key()? as &(dyn Param + Send)).add(&mut args)?;
+                }
+                #[cfg(feature = "postgres")]
+                let sql = pg_replace_placeholder(&sql);
+
+                debug!(target: "sorm", "{}", sql);
+                rows += sqlx::query_with(&sql, args)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected();
+            }
+        }
+        tx.commit().await?;
+        for (model, _) in models.iter_mut().zip(&included).filter(|v| *v.1) {
+            model.flush();
+        }
+        Ok(rows)
+    }
+
+    /// Finds the first model whose columns equal the fields set by `attrs`, or returns a new model
+    /// filled with `attrs` and `defaults`, without inserting it.
+    ///
+    /// Returns [`crate::Error::NoWhereClause`] if `attrs` sets no field. A field set to `None`
+    /// matches `NULL`.
+    async fn first_or_new(
+        executor: impl Executor<'_, Database = Database>,
+        attrs: impl Fill<Self>,
+        defaults: impl Fill<Self>,
+    ) -> crate::Result<Self> {
+        let mut model = Self::from(attrs);
+        let columns = lookup_columns(&model)?;
+        if let Some(found) = first_by(executor, &model, &columns).await? {
+            return Ok(found);
+        }
+        model.fill(defaults);
+        Ok(model)
+    }
+
+    /// Finds the first model whose columns equal the fields set by `attrs`, or inserts one filled
+    /// with `attrs` and `defaults`.
+    ///
+    /// The model is inserted then selected again. With a unique index on the `attrs` columns, a
+    /// model inserted meanwhile by another client is returned instead of a duplicate, without
+    /// one the table needs no index. The insert is `INSERT ... ON CONFLICT DO NOTHING` on
+    /// PostgreSQL and SQLite, and a plain `INSERT` whose duplicate key error is caught on MySQL.
+    /// If the insert conflicts on another unique index, the error is returned.
+    async fn first_or_create<'c>(
+        executor: impl Acquire<'c, Database = Database>,
+        attrs: impl Fill<Self>,
+        defaults: impl Fill<Self>,
+    ) -> crate::Result<Self> {
+        let mut model = Self::from(attrs);
+        let columns = lookup_columns(&model)?;
+        let mut conn = executor.acquire().await?;
+        if let Some(found) = first_by(&mut *conn, &model, &columns).await? {
+            return Ok(found);
+        }
+        model.fill(defaults);
+        Ok(insert_or_find(&mut conn, &mut model, &columns).await?.0)
+    }
+
+    /// Finds the first model whose columns equal the fields set by `attrs` and updates it with
+    /// `values`, or inserts one filled with `attrs` and `values`.
+    ///
+    /// The `update_time` fields are filled on update. Like [`Model::first_or_create`], a model
+    /// inserted meanwhile by another client is updated instead of duplicated, with the same
+    /// requirements on the unique index.
+    async fn update_or_create<'c>(
+        executor: impl Acquire<'c, Database = Database>,
+        attrs: impl Fill<Self>,
+        values: impl Fill<Self>,
+    ) -> crate::Result<Self> {
+        let mut model = Self::from(attrs);
+        let columns = lookup_columns(&model)?;
+        model.fill(values);
+        let mut set: Vec<_> = model
+            .collect_filled()
+            .into_iter()
+            .map(|v| v.0)
+            .filter(|v| !columns.contains(v))
+            .collect();
+
+        let mut conn = executor.acquire().await?;
+        let found = match first_by(&mut *conn, &model, &columns).await? {
+            Some(found) => found,
+            None => match insert_or_find(&mut conn, &mut model, &columns).await? {
+                (found, true) => return Ok(found),
+                (found, false) => found,
+            },
+        };
+        if set.is_empty() {
+            return Ok(found);
+        }
+
+        // The create defaults may have been filled by the insert, only the update_time fields
+        // are written besides the values.
+        let mut update_time = Self::default();
+        update_time.fill_update_default();
+        for (column, _) in update_time.collect_changed() {
+            if !set.contains(&column) && !columns.contains(&column) {
+                set.push(column);
+            }
+        }
+        model.fill_update_default();
+        let primary_key = found.primary_key()?;
+
+        let fields = model.collect_filled();
+        let mut sql = String::with_capacity(32 + set.len() * 10);
+        let mut args = crate::Arguments::default();
+        args.reserve(set.len() + 1, set.len() + 1);
+        sql.push_str("UPDATE ");
+        concat_ident(&mut sql, Self::TABLE);
+        sql.push_str(" SET ");
+        push_fields(&mut sql, &set);
+        for column in &set {
+            if let Some(field) = fields.iter().find(|v| v.0 == *column) {
+                field.1.add(&mut args)?;
+            }
+        }
+        sql.push_str(" WHERE ");
+        concat_ident(&mut sql, Self::PRIMARY_KEY);
+        sql.push_str("=?");
+        (primary_key as &(dyn Param + Send)).add(&mut args)?;
+        #[cfg(feature = "postgres")]
+        let sql = pg_replace_placeholder(&sql);
+
+        debug!(target: "sorm", "{}", sql);
+        sqlx::query_with(&sql, args).execute(&mut *conn).await?;
+        Self::find(&mut *conn, primary_key).await
+    }
+
     /// Deletes the model.
     #[inline]
     async fn delete(&self, executor: impl Executor<'_, Database = Database>) -> crate::Result<u64> {
@@ -300,6 +636,205 @@ pub trait Model: Default + for<'r> FromRow<'r, Row> + Send + Unpin + 'static {
     }
 }
 
+/// Writes ` IN (?,?,...)` with `count` placeholders.
+fn push_in(sql: &mut String, count: usize) {
+    sql.push_str(" IN (");
+    for _ in 0..count {
+        sql.push_str("?,");
+    }
+    sql.pop();
+    sql.push(')');
+}
+
+/// Writes `col=?` for each of `columns`, separated by commas.
+fn push_fields(sql: &mut String, columns: &[&str]) {
+    for column in columns {
+        concat_ident(sql, column);
+        sql.push_str("=?,");
+    }
+    sql.pop();
+}
+
+/// Returns the columns of the fields set on `model`, to look it up.
+fn lookup_columns<M: Model>(model: &M) -> crate::Result<Vec<&'static str>> {
+    let columns: Vec<_> = model.collect_filled().into_iter().map(|v| v.0).collect();
+    if columns.is_empty() {
+        return Err(crate::Error::NoWhereClause);
+    }
+    Ok(columns)
+}
+
+/// Finds the first model whose `columns` equal the fields of `model`.
+async fn first_by<M: Model>(
+    executor: impl Executor<'_, Database = Database>,
+    model: &M,
+    columns: &[&str],
+) -> crate::Result<Option<M>> {
+    let fields = model.collect_filled();
+    let mut sql = String::with_capacity(48 + M::COLUMNS.len() * 10 + columns.len() * 10);
+    let mut args = crate::Arguments::default();
+    args.reserve(columns.len(), columns.len());
+    sql.push_str("SELECT ");
+    concat_idents(&mut sql, M::COLUMNS);
+    sql.push_str(" FROM ");
+    concat_ident(&mut sql, M::TABLE);
+    sql.push_str(" WHERE ");
+    for field in fields.iter().filter(|v| columns.contains(&v.0)) {
+        concat_ident(&mut sql, field.0);
+        // `col=NULL` never matches.
+        if field.1.is_null() {
+            sql.push_str(" IS NULL AND ");
+        } else {
+            sql.push_str("=? AND ");
+            field.1.add(&mut args)?;
+        }
+    }
+    sql.truncate(sql.len() - 5);
+    sql.push_str(" LIMIT 1");
+    #[cfg(feature = "postgres")]
+    let sql = pg_replace_placeholder(&sql);
+
+    debug!(target: "sorm", "{}", sql);
+    Ok(sqlx::query_as_with(&sql, args)
+        .fetch_optional(executor)
+        .await?)
+}
+
+/// Inserts the model unless a row with the same `columns` exists, then selects that row,
+/// returning it with whether it was inserted.
+///
+/// A conflict is ignored without conflict target, since the `columns` may have no unique index,
+/// and `INSERT IGNORE` would ignore other errors than conflicts on MySQL. A conflict on another
+/// unique index is detected when the row is not found afterwards.
+async fn insert_or_find<M: Model>(
+    conn: &mut <Database as sqlx::Database>::Connection,
+    model: &mut M,
+    columns: &[&str],
+) -> crate::Result<(M, bool)> {
+    model.fill_create_default();
+    let fields = model.collect_filled();
+    let mut sql = String::with_capacity(48 + fields.len() * 12 + columns.len() * 10);
+    let mut args = crate::Arguments::default();
+    args.reserve(fields.len(), fields.len());
+    sql.push_str("INSERT INTO ");
+    concat_ident(&mut sql, M::TABLE);
+    sql.push_str(" (");
+    for field in &fields {
+        concat_ident(&mut sql, field.0);
+        sql.push(',');
+        field.1.add(&mut args)?;
+    }
+    sql.pop();
+    sql.push_str(") VALUES (");
+    for _ in &fields {
+        sql.push_str("?,");
+    }
+    sql.pop();
+    sql.push(')');
+    #[cfg(not(feature = "mysql"))]
+    sql.push_str(" ON CONFLICT DO NOTHING");
+    #[cfg(feature = "postgres")]
+    let sql = pg_replace_placeholder(&sql);
+
+    debug!(target: "sorm", "{}", sql);
+    let (inserted, conflict) = match sqlx::query_with(&sql, args).execute(&mut *conn).await {
+        Ok(result) => (result.rows_affected() > 0, None),
+        #[cfg(feature = "mysql")]
+        Err(sqlx::Error::Database(err))
+            if err.kind() == sqlx::error::ErrorKind::UniqueViolation =>
+        {
+            (false, Some(sqlx::Error::Database(err)))
+        }
+        Err(err) => return Err(err.into()),
+    };
+    match first_by(&mut *conn, &*model, columns).await? {
+        Some(found) => Ok((found, inserted)),
+        // The conflict is on another unique index.
+        None => Err(conflict.unwrap_or(sqlx::Error::RowNotFound).into()),
+    }
+}
+
+async fn update_counter<'c, M: Model>(
+    model: &mut M,
+    executor: impl Acquire<'c, Database = Database>,
+    column: &str,
+    op: &str,
+    by: &(dyn for<'q> Param<'q> + Sync),
+) -> crate::Result<()> {
+    if !M::COLUMNS.contains(&column) {
+        return Err(crate::Error::UnknownColumn(column.to_owned()));
+    }
+    let primary_key = model.primary_key()?;
+
+    // Only the `update_time` fields are written besides the counter, the other changed fields
+    // of the model are left for the next update.
+    let mut update_time = M::default();
+    update_time.fill_update_default();
+    let mut fields = update_time.collect_changed();
+    fields.retain(|v| v.0 != column);
+    let mut sql = String::with_capacity(48 + fields.len() * 20);
+    let mut args = crate::Arguments::default();
+    args.reserve(fields.len() + 2, fields.len() + 2);
+    sql.push_str("UPDATE ");
+    concat_ident(&mut sql, M::TABLE);
+    sql.push_str(" SET ");
+    concat_ident(&mut sql, column);
+    sql.push('=');
+    concat_ident(&mut sql, column);
+    sql.push_str(op);
+    sql.push('?');
+    by.add(&mut args)?;
+    for field in &fields {
+        sql.push(',');
+        concat_ident(&mut sql, field.0);
+        sql.push_str("=?");
+        field.1.add(&mut args)?;
+    }
+    sql.push_str(" WHERE ");
+    concat_ident(&mut sql, M::PRIMARY_KEY);
+    sql.push_str("=?");
+    (primary_key as &(dyn Param + Send)).add(&mut args)?;
+
+    let mut columns = Vec::with_capacity(fields.len() + 1);
+    columns.push(column);
+    columns.extend(fields.iter().map(|v| v.0));
+    let mut conn = executor.acquire().await?;
+    #[cfg(any(feature = "sqlite", feature = "postgres"))]
+    let row = {
+        sql.push_str(" RETURNING ");
+        concat_idents(&mut sql, &columns);
+        #[cfg(feature = "postgres")]
+        let sql = pg_replace_placeholder(&sql);
+        debug!(target: "sorm", "{}", sql);
+        sqlx::query_with(&sql, args).fetch_one(&mut *conn).await?
+    };
+    #[cfg(feature = "mysql")]
+    let row = {
+        use sqlx::Connection;
+
+        let mut tx = Connection::begin(&mut *conn).await?;
+        debug!(target: "sorm", "{}", sql);
+        sqlx::query_with(&sql, args).execute(&mut *tx).await?;
+
+        let mut sql = String::with_capacity(32 + columns.len() * 10);
+        sql.push_str("SELECT ");
+        concat_idents(&mut sql, &columns);
+        sql.push_str(" FROM ");
+        concat_ident(&mut sql, M::TABLE);
+        sql.push_str(" WHERE ");
+        concat_ident(&mut sql, M::PRIMARY_KEY);
+        sql.push_str("=?");
+        let mut args = crate::Arguments::default();
+        (primary_key as &(dyn Param + Send)).add(&mut args)?;
+        debug!(target: "sorm", "{}", sql);
+        let row = sqlx::query_with(&sql, args).fetch_one(&mut *tx).await?;
+        tx.commit().await?;
+        row
+    };
+    model.load_row(&row)?;
+    Ok(())
+}
+
 /// Wrapper struct for [`crate::query::Query`] which decodes rows into the type `T`.
 pub struct Query<'q, T>
 where
@@ -313,6 +848,13 @@ impl<'q, T> Query<'q, T>
 where
     T: for<'r> FromRow<'r, Row> + Send + Unpin,
 {
+    /// See [`crate::query::Query::alias`]
+    #[inline]
+    pub fn alias(&mut self, alias: &'q str) -> &mut Self {
+        self.query.alias(alias);
+        self
+    }
+
     /// See [`crate::query::Query::select`]
     #[inline]
     pub fn select(&mut self, fields: &'q [&str]) -> &mut Self {
@@ -320,9 +862,47 @@ where
         self
     }
 
+    /// See [`crate::query::Query::with`]
+    #[inline]
+    pub fn with(
+        &mut self,
+        name: &'q str,
+        query: &'q impl AsRef<crate::query::Query<'q>>,
+    ) -> &mut Self {
+        self.query.with(name, query);
+        self
+    }
+
+    /// See [`crate::query::Query::with_recursive`]
+    #[inline]
+    pub fn with_recursive(
+        &mut self,
+        name: &'q str,
+        anchor: &'q impl AsRef<crate::query::Query<'q>>,
+        recursive: &'q impl AsRef<crate::query::Query<'q>>,
+    ) -> &mut Self {
+        self.query.with_recursive(name, anchor, recursive);
+        self
+    }
+
+    /// See [`crate::query::Query::distinct`]
+    #[inline]
+    pub fn distinct(&mut self) -> &mut Self {
+        self.query.distinct();
+        self
+    }
+
+    /// See [`crate::query::Query::distinct_on`]
+    #[cfg(feature = "postgres")]
+    #[inline]
+    pub fn distinct_on(&mut self, columns: &'q [&'q str]) -> &mut Self {
+        self.query.distinct_on(columns);
+        self
+    }
+
     /// See [`crate::query::Query::select_raw`]
     #[inline]
-    pub fn select_raw(&mut self, expr: &'q str) -> &mut Self {
+    pub fn select_raw(&mut self, expr: impl Clause<'q>) -> &mut Self {
         self.query.select_raw(expr);
         self
     }
@@ -337,6 +917,34 @@ where
         self
     }
 
+    /// See [`crate::query::Query::join`]
+    #[inline]
+    pub fn join(&mut self, table: impl Table<'q>, on: impl Clause<'q>) -> &mut Self {
+        self.query.join(table, on);
+        self
+    }
+
+    /// See [`crate::query::Query::left_join`]
+    #[inline]
+    pub fn left_join(&mut self, table: impl Table<'q>, on: impl Clause<'q>) -> &mut Self {
+        self.query.left_join(table, on);
+        self
+    }
+
+    /// See [`crate::query::Query::right_join`]
+    #[inline]
+    pub fn right_join(&mut self, table: impl Table<'q>, on: impl Clause<'q>) -> &mut Self {
+        self.query.right_join(table, on);
+        self
+    }
+
+    /// See [`crate::query::Query::cross_join`]
+    #[inline]
+    pub fn cross_join(&mut self, table: impl Table<'q>) -> &mut Self {
+        self.query.cross_join(table);
+        self
+    }
+
     /// See [`crate::query::Query:: where `]
     #[inline]
     pub fn r#where(&mut self, clause: impl Clause<'q>) -> &mut Self {
@@ -351,6 +959,66 @@ where
         self
     }
 
+    /// See [`crate::query::Query::where_group`]
+    #[inline]
+    pub fn where_group(&mut self, f: impl FnOnce(&mut crate::query::Query<'q>)) -> &mut Self {
+        self.query.where_group(f);
+        self
+    }
+
+    /// See [`crate::query::Query::or_where_group`]
+    #[inline]
+    pub fn or_where_group(&mut self, f: impl FnOnce(&mut crate::query::Query<'q>)) -> &mut Self {
+        self.query.or_where_group(f);
+        self
+    }
+
+    /// See [`crate::query::Query::where_in_sub`]
+    #[inline]
+    pub fn where_in_sub(
+        &mut self,
+        column: &'q str,
+        query: &'q impl AsRef<crate::query::Query<'q>>,
+    ) -> &mut Self {
+        self.query.where_in_sub(column, query);
+        self
+    }
+
+    /// See [`crate::query::Query::where_exists`]
+    #[inline]
+    pub fn where_exists(&mut self, query: &'q impl AsRef<crate::query::Query<'q>>) -> &mut Self {
+        self.query.where_exists(query);
+        self
+    }
+
+    /// See [`crate::query::Query::union`]
+    #[inline]
+    pub fn union(&mut self, query: &'q impl AsRef<crate::query::Query<'q>>) -> &mut Self {
+        self.query.union(query);
+        self
+    }
+
+    /// See [`crate::query::Query::union_all`]
+    #[inline]
+    pub fn union_all(&mut self, query: &'q impl AsRef<crate::query::Query<'q>>) -> &mut Self {
+        self.query.union_all(query);
+        self
+    }
+
+    /// See [`crate::query::Query::intersect`]
+    #[inline]
+    pub fn intersect(&mut self, query: &'q impl AsRef<crate::query::Query<'q>>) -> &mut Self {
+        self.query.intersect(query);
+        self
+    }
+
+    /// See [`crate::query::Query::except`]
+    #[inline]
+    pub fn except(&mut self, query: &'q impl AsRef<crate::query::Query<'q>>) -> &mut Self {
+        self.query.except(query);
+        self
+    }
+
     /// See [`crate::query::Query::group_by`]
     #[inline]
     pub fn group_by(&mut self, fields: &'q [&str]) -> &mut Self {
@@ -360,7 +1028,7 @@ where
 
     /// See [`crate::query::Query::group_by_raw`]
     #[inline]
-    pub fn group_by_raw(&mut self, expr: &'q str) -> &mut Self {
+    pub fn group_by_raw(&mut self, expr: impl Clause<'q>) -> &mut Self {
         self.query.group_by_raw(expr);
         self
     }
@@ -379,6 +1047,20 @@ where
         self
     }
 
+    /// See [`crate::query::Query::having_group`]
+    #[inline]
+    pub fn having_group(&mut self, f: impl FnOnce(&mut crate::query::Query<'q>)) -> &mut Self {
+        self.query.having_group(f);
+        self
+    }
+
+    /// See [`crate::query::Query::or_having_group`]
+    #[inline]
+    pub fn or_having_group(&mut self, f: impl FnOnce(&mut crate::query::Query<'q>)) -> &mut Self {
+        self.query.or_having_group(f);
+        self
+    }
+
     /// See [`crate::query::Query::order_by`]
     #[inline]
     pub fn order_by(&mut self, order_by: &'q str) -> &mut Self {
@@ -395,11 +1077,22 @@ where
 
     /// See [`crate::query::Query::order_by_raw`]
     #[inline]
-    pub fn order_by_raw(&mut self, order_by: &'q str) -> &mut Self {
+    pub fn order_by_raw(&mut self, order_by: impl Clause<'q>) -> &mut Self {
         self.query.order_by_raw(order_by);
         self
     }
 
+    /// See [`crate::query::Query::order_by_checked`]
+    #[inline]
+    pub fn order_by_checked(
+        &mut self,
+        input: &'q str,
+        allowed: &[&str],
+    ) -> crate::Result<&mut Self> {
+        self.query.order_by_checked(input, allowed)?;
+        Ok(self)
+    }
+
     /// See [`crate::query::Query::offset`]
     #[inline]
     pub fn offset(&mut self, offset: usize) -> &mut Self {
@@ -414,6 +1107,34 @@ where
         self
     }
 
+    /// See [`crate::query::Query::lock_for_update`]
+    #[inline]
+    pub fn lock_for_update(&mut self) -> &mut Self {
+        self.query.lock_for_update();
+        self
+    }
+
+    /// See [`crate::query::Query::lock_for_share`]
+    #[inline]
+    pub fn lock_for_share(&mut self) -> &mut Self {
+        self.query.lock_for_share();
+        self
+    }
+
+    /// See [`crate::query::Query::skip_locked`]
+    #[inline]
+    pub fn skip_locked(&mut self) -> &mut Self {
+        self.query.skip_locked();
+        self
+    }
+
+    /// See [`crate::query::Query::nowait`]
+    #[inline]
+    pub fn nowait(&mut self) -> &mut Self {
+        self.query.nowait();
+        self
+    }
+
     /// See [`crate::query::Query::plunk`]
     #[inline]
     pub async fn plunk<U>(
@@ -426,6 +1147,18 @@ where
         self.query.plunk(executor).await
     }
 
+    /// See [`crate::query::Query::fetch_plunk`]
+    #[inline]
+    pub fn fetch_plunk<'e, U>(
+        &'e self,
+        executor: impl Executor<'e, Database = Database> + 'e,
+    ) -> BoxStream<'e, crate::Result<U>>
+    where
+        U: for<'r> Decode<'r, Database> + Type<Database> + Send + 'e,
+    {
+        self.query.fetch_plunk(executor)
+    }
+
     /// See [`crate::query::Query::value`]
     #[inline]
     pub async fn value<U>(
@@ -459,6 +1192,18 @@ where
         self.query.get(executor).await
     }
 
+    /// See [`crate::query::Query::fetch`]
+    #[inline]
+    pub fn fetch<'e>(
+        &'e self,
+        executor: impl Executor<'e, Database = Database> + 'e,
+    ) -> BoxStream<'e, crate::Result<T>>
+    where
+        T: 'e,
+    {
+        self.query.fetch(executor)
+    }
+
     /// See [`crate::query::Query::find`]
     #[inline]
     pub async fn find(&self, executor: impl Executor<'q, Database = Database>) -> crate::Result<T> {
@@ -474,6 +1219,92 @@ where
         self.query.find_optional(executor).await
     }
 
+    /// See [`crate::query::Query::count`]
+    #[inline]
+    pub async fn count(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+    ) -> crate::Result<u64> {
+        self.query.count(executor).await
+    }
+
+    /// See [`crate::query::Query::exists`]
+    #[inline]
+    pub async fn exists(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+    ) -> crate::Result<bool> {
+        self.query.exists(executor).await
+    }
+
+    /// See [`crate::query::Query::sum`]
+    #[inline]
+    pub async fn sum<U: Number>(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+        column: &str,
+    ) -> crate::Result<U> {
+        self.query.sum(executor, column).await
+    }
+
+    /// See [`crate::query::Query::avg`]
+    #[inline]
+    pub async fn avg<U: Number>(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+        column: &str,
+    ) -> crate::Result<Option<U>> {
+        self.query.avg(executor, column).await
+    }
+
+    /// See [`crate::query::Query::min`]
+    #[inline]
+    pub async fn min<U>(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+        column: &str,
+    ) -> crate::Result<Option<U>>
+    where
+        U: for<'r> Decode<'r, Database> + Type<Database>,
+    {
+        self.query.min(executor, column).await
+    }
+
+    /// See [`crate::query::Query::max`]
+    #[inline]
+    pub async fn max<U>(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+        column: &str,
+    ) -> crate::Result<Option<U>>
+    where
+        U: for<'r> Decode<'r, Database> + Type<Database>,
+    {
+        self.query.max(executor, column).await
+    }
+
+    /// See [`crate::query::Query::paginate`]
+    #[inline]
+    pub async fn paginate<'c>(
+        &self,
+        executor: impl Acquire<'c, Database = Database>,
+        page: u64,
+        per_page: u64,
+    ) -> crate::Result<Page<T>> {
+        self.query.paginate(executor, page, per_page).await
+    }
+
+    /// See [`crate::query::Query::cursor_paginate`]
+    #[inline]
+    pub async fn cursor_paginate(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+        cursor: Option<&str>,
+        per_page: u64,
+    ) -> crate::Result<CursorPage<T>> {
+        self.query.cursor_paginate(executor, cursor, per_page).await
+    }
+
     /// See [`crate::query::Query::delete`]
     #[inline]
     pub async fn delete(
@@ -491,6 +1322,76 @@ where
     ) -> crate::Result<u64> {
         self.query.update(executor, update).await
     }
+
+    /// See [`crate::query::Query::to_sql`]
+    #[inline]
+    pub fn to_sql(&self) -> Sql {
+        self.query.to_sql()
+    }
+
+    /// See [`crate::query::Query::to_update_sql`]
+    #[inline]
+    pub fn to_update_sql(&self, update: impl Clause<'q>) -> crate::Result<Sql> {
+        self.query.to_update_sql(update)
+    }
+
+    /// See [`crate::query::Query::to_delete_sql`]
+    #[inline]
+    pub fn to_delete_sql(&self) -> crate::Result<Sql> {
+        self.query.to_delete_sql()
+    }
+
+    /// See [`crate::query::Query::explain`]
+    #[inline]
+    pub async fn explain(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+    ) -> crate::Result<Vec<Row>> {
+        self.query.explain(executor).await
+    }
+}
+
+impl<'q, T: Model> Query<'q, T> {
+    /// Walks the rows in chunks of `size`, ordered by the primary key.
+    ///
+    /// Returns [`crate::Error::NoPrimaryKey`] if the model has no primary key.
+    /// See [`crate::query::Query::chunk_by`]
+    #[inline]
+    pub async fn chunk_by_id<'c, F, Fut>(
+        &self,
+        executor: impl Acquire<'c, Database = Database>,
+        size: usize,
+        f: F,
+    ) -> crate::Result<()>
+    where
+        F: FnMut(Vec<T>) -> Fut,
+        Fut: Future<Output = crate::Result<bool>>,
+    {
+        if T::PRIMARY_KEY.is_empty() {
+            return Err(crate::Error::NoPrimaryKey);
+        }
+        self.query.chunk_by(executor, T::PRIMARY_KEY, size, f).await
+    }
+}
+
+impl<'q, T> AsRef<crate::query::Query<'q>> for Query<'q, T>
+where
+    T: for<'r> FromRow<'r, Row> + Send + Unpin,
+{
+    #[inline]
+    fn as_ref(&self) -> &crate::query::Query<'q> {
+        &self.query
+    }
+}
+
+impl<'q, T> AsMut<crate::query::Query<'q>> for Query<'q, T>
+where
+    T: for<'r> FromRow<'r, Row> + Send + Unpin,
+{
+    #[inline]
+    fn as_mut(&mut self) -> &mut crate::query::Query<'q> {
+        &mut self.query
+    }
 }
 
 /// Used to fill a model.
@@ -499,11 +1400,6 @@ pub trait Fill<T: Model> {
     fn fill(self, model: &mut T);
 }
 
-#[cfg(not(feature = "test"))]
-/// Represents the type of the primary key for models without primary key.
-pub enum HasNoPrimaryKey {}
-
-#[cfg(feature = "test")]
 /// Represents the type of the primary key for models without primary key.
 #[derive(Debug)]
 pub enum HasNoPrimaryKey {}
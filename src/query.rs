@@ -1,10 +1,21 @@
 //! A query builder for constructing SELECT, INSERT, UPDATE, or DELETE queries.
+use std::borrow::Cow;
 use std::collections::HashSet;
+use std::future::Future;
+use std::ops::Deref;
 
+use crate::cursor::{Cursor, Value};
+use crate::dialect::Dialect;
 use crate::{concat_ident, concat_idents, Clause, Database, Error, Param};
+use async_stream::try_stream;
+use futures_core::stream::BoxStream;
 use log::debug;
 use sqlx::error::BoxDynError;
-use sqlx::{Arguments, Decode, Executor, FromRow, Row, Type};
+use sqlx::{Acquire, Arguments, Decode, Executor, FromRow, Row, Type};
+
+mod owned;
+
+pub use owned::{ClauseOwned, ParamOwned, QueryOwned};
 
 #[cfg(feature = "test")]
 pub mod test {
@@ -24,10 +35,14 @@ pub mod test {
         }
 
         pub fn add(&self, sql: &str, params: &[&(dyn Param + Sync)]) {
-            self.query.lock().unwrap().push((
+            self.push(
                 sql.to_string(),
                 params.to_vec().into_iter().map(|v| v.to_string()).collect(),
-            ));
+            );
+        }
+
+        pub fn push(&self, sql: String, params: Vec<String>) {
+            self.query.lock().unwrap().push((sql, params));
         }
 
         pub fn take(&self) -> Vec<(String, Vec<String>)> {
@@ -38,17 +53,248 @@ pub mod test {
     pub static QUERY: Query = Query::new();
 }
 
+/// The parameters of a clause, owned when built from an owned query.
+type Params<'q> = Cow<'q, [&'q (dyn Param<'q> + Sync)]>;
+
+#[derive(Clone)]
 enum Select<'q> {
-    Columns(&'q [&'q str]),
-    Raw(&'q str),
+    Columns(Cow<'q, [&'q str]>),
+    Raw(&'q str, Params<'q>),
     Omitted(HashSet<&'q str>),
     None,
 }
 
+/// Represents a table, optionally with an alias.
+///
+/// It is implemented for `&str` and for `(&str, &str)`, where the second element is the alias.
+pub trait Table<'q> {
+    /// Returns the table name.
+    fn name(&self) -> &'q str;
+
+    /// Returns the table alias.
+    fn alias(&self) -> Option<&'q str>;
+}
+
+impl<'q> Table<'q> for &'q str {
+    #[inline]
+    fn name(&self) -> &'q str {
+        self
+    }
+
+    #[inline]
+    fn alias(&self) -> Option<&'q str> {
+        None
+    }
+}
+
+impl<'q> Table<'q> for (&'q str, &'q str) {
+    #[inline]
+    fn name(&self) -> &'q str {
+        self.0
+    }
+
+    #[inline]
+    fn alias(&self) -> Option<&'q str> {
+        Some(self.1)
+    }
+}
+
+/// A subquery, owned when built from an owned query.
+#[derive(Clone)]
+enum SubQuery<'q> {
+    Borrowed(&'q Query<'q>),
+    Owned(Box<Query<'q>>),
+}
+
+impl<'q> Deref for SubQuery<'q> {
+    type Target = Query<'q>;
+
+    #[inline]
+    fn deref(&self) -> &Query<'q> {
+        match self {
+            SubQuery::Borrowed(query) => query,
+            SubQuery::Owned(query) => query,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Source<'q> {
+    Table(&'q str),
+    Sub(SubQuery<'q>),
+}
+
+#[derive(Clone)]
+enum Condition<'q> {
+    And,
+    Or,
+    Clause(&'q str, Params<'q>),
+    In(&'q str, SubQuery<'q>),
+    Exists(SubQuery<'q>),
+    Group(Vec<Condition<'q>>),
+}
+
+#[derive(Clone)]
+struct Cte<'q> {
+    name: &'q str,
+    query: SubQuery<'q>,
+    /// The recursive member, combined with the anchor member `query` using UNION ALL.
+    recursive: Option<SubQuery<'q>>,
+}
+
+#[derive(Clone)]
+struct Join<'q> {
+    kind: &'static str,
+    table: &'q str,
+    alias: Option<&'q str>,
+    on: Option<(&'q str, Params<'q>)>,
+}
+
+#[derive(Clone)]
 enum OrderBy<'q> {
     Asc(&'q str),
     Desc(&'q str),
-    Raw(&'q str),
+    /// A column in descending order if `true`, with the position of the NULL values.
+    Nulls(&'q str, bool, Nulls),
+    Raw(&'q str, Params<'q>),
+}
+
+/// The position of the NULL values in an ordering, see [`Query::order_by_checked`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Nulls {
+    /// NULLS FIRST.
+    First,
+    /// NULLS LAST.
+    Last,
+}
+
+/// A column of a sort input, in descending order if `true`, with the position of the NULL
+/// values if any.
+pub(crate) type SortField<'a> = (&'a str, bool, Option<Nulls>);
+
+/// Parses a comma separated sort input, e.g. `-created_at:nulls_last,name`, as accepted by
+/// [`Query::order_by_checked`] and [`crate::filter::Filter::parse`].
+///
+/// The whitespace around the entries is ignored and empty entries are skipped. Returns the
+/// entry whose suffix is neither `:nulls_first` nor `:nulls_last` on error. The columns are
+/// not checked.
+pub(crate) fn parse_sort(input: &str) -> Result<Vec<SortField<'_>>, &str> {
+    let mut fields = Vec::new();
+    for entry in input.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+        let (column, nulls) = match entry.split_once(':') {
+            Some((column, "nulls_first")) => (column, Some(Nulls::First)),
+            Some((column, "nulls_last")) => (column, Some(Nulls::Last)),
+            Some(_) => return Err(entry),
+            None => (entry, None),
+        };
+        match column.strip_prefix('-') {
+            Some(column) => fields.push((column, true, nulls)),
+            None => fields.push((column, false, nulls)),
+        }
+    }
+    Ok(fields)
+}
+
+/// Parses a sort input with [`parse_sort`], returning [`Error::InvalidOrderBy`] for malformed
+/// entries and for columns that are not one of `allowed`.
+pub(crate) fn parse_sort_checked<'a>(
+    input: &'a str,
+    allowed: &[&str],
+) -> crate::Result<Vec<SortField<'a>>> {
+    let fields = parse_sort(input)
+        .map_err(|v| Error::InvalidOrderBy(format!("invalid ordering `{}`", v)))?;
+    match fields.iter().find(|v| !allowed.contains(&v.0)) {
+        Some(v) => Err(Error::InvalidOrderBy(format!(
+            "column `{}` is not allowed",
+            v.0
+        ))),
+        None => Ok(fields),
+    }
+}
+
+/// A page of items returned by [`Query::paginate`].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Page<T> {
+    /// The items of the page.
+    pub items: Vec<T>,
+    /// The total number of items.
+    pub total: u64,
+    /// The current page, starting at 1.
+    pub page: u64,
+    /// The maximum number of items per page.
+    pub per_page: u64,
+    /// The last page, which is at least 1.
+    pub last_page: u64,
+}
+
+/// A page of items returned by [`Query::cursor_paginate`].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CursorPage<T> {
+    /// The items of the page.
+    pub items: Vec<T>,
+    /// The cursor of the next page, if any.
+    pub next_cursor: Option<String>,
+    /// The cursor of the previous page, if any.
+    pub prev_cursor: Option<String>,
+}
+
+/// A SQL statement with its parameters, returned by [`Query::to_sql`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sql {
+    /// The SQL, with the placeholders of the backend.
+    pub sql: String,
+    /// The printable representation of the parameters, in placeholder order, see
+    /// [`crate::Param::to_string`].
+    pub params: Vec<String>,
+}
+
+impl Sql {
+    fn new(sql: String, params: &[&(dyn Param + Sync)]) -> Self {
+        Self {
+            sql,
+            params: params.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for Sql {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.sql)?;
+        if !self.params.is_empty() {
+            write!(f, " -- [{}]", self.params.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// A numeric type the results of [`Query::sum`] and [`Query::avg`] are decoded to.
+///
+/// The aggregate is cast to the SQL type matching the Rust type, since the backends return
+/// different types, e.g. the sum of a `BIGINT` column is a `NUMERIC` in PostgreSQL but an
+/// `INTEGER` in SQLite.
+pub trait Number: for<'r> Decode<'r, Database> + Type<Database> {
+    /// The SQL type the aggregate is cast to.
+    const SQL_TYPE: &'static str;
+}
+
+impl Number for i64 {
+    #[cfg(feature = "sqlite")]
+    const SQL_TYPE: &'static str = "INTEGER";
+    #[cfg(feature = "mysql")]
+    const SQL_TYPE: &'static str = "SIGNED";
+    #[cfg(feature = "postgres")]
+    const SQL_TYPE: &'static str = "BIGINT";
+}
+
+impl Number for f64 {
+    #[cfg(feature = "sqlite")]
+    const SQL_TYPE: &'static str = "REAL";
+    #[cfg(feature = "mysql")]
+    const SQL_TYPE: &'static str = "DOUBLE";
+    #[cfg(feature = "postgres")]
+    const SQL_TYPE: &'static str = "DOUBLE PRECISION";
 }
 
 /// Represents the fields to update.
@@ -111,6 +357,39 @@ impl<'q> Update<'q> {
         self
     }
 
+    /// Increments a column by `by`, atomically in the database.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Update;
+    /// let update = Update::new().increment("views", &1).set("name", &"foo");
+    /// // `views`=`views`+?,`name`=?
+    /// ```
+    #[inline]
+    pub fn increment(self, column: &'q str, by: &'q (dyn Param<'q> + Sync)) -> Self {
+        self.add_to(column, "+", by)
+    }
+
+    /// Decrements a column by `by`, atomically in the database.
+    #[inline]
+    pub fn decrement(self, column: &'q str, by: &'q (dyn Param<'q> + Sync)) -> Self {
+        self.add_to(column, "-", by)
+    }
+
+    fn add_to(mut self, column: &'q str, op: &str, by: &'q (dyn Param<'q> + Sync)) -> Self {
+        if !self.expr.is_empty() {
+            self.expr.push(',');
+        }
+        concat_ident(&mut self.expr, column);
+        self.expr.push('=');
+        concat_ident(&mut self.expr, column);
+        self.expr.push_str(op);
+        self.expr.push('?');
+        self.params.push(by);
+        self
+    }
+
     /// Sets a column to a raw SQL value for an update operation.
     pub fn set_raw(mut self, column: &'q str, value: &'q str) -> Self {
         if !self.expr.is_empty() {
@@ -135,31 +414,184 @@ impl<'q> Clause<'q> for &'q Update<'q> {
     }
 }
 
+/// Represents the rows to insert.
+///
+/// The columns are set on the first row, the following rows started with [`Insert::row`]
+/// must set the same columns in the same order, otherwise executing the query returns
+/// [`Error::InvalidInsert`]. An `Insert` can also select its rows from a query with
+/// [`Insert::select`].
+///
+/// # Examples
+///
+/// ```rust
+/// use sorm::query::{Insert, Query};
+/// use sorm::{Result, Database};
+/// async fn insert(db: impl sqlx::Executor<'_, Database = Database>) -> Result<u64> {
+///     Query::table("users")
+///         .insert(
+///             db,
+///             &Insert::new()
+///                 .set("name", &"foo")
+///                 .set_raw("created_at", "CURRENT_TIMESTAMP")
+///                 .row()
+///                 .set("name", &"bar")
+///                 .set_raw("created_at", "CURRENT_TIMESTAMP"),
+///         )
+///         .await
+///     // INSERT INTO `users` (`name`,`created_at`) VALUES (?,CURRENT_TIMESTAMP),(?,CURRENT_TIMESTAMP)
+/// }
+/// ```
+pub struct Insert<'q> {
+    columns: Vec<&'q str>,
+    values: String,
+    params: Vec<&'q (dyn Param<'q> + Sync)>,
+    select: Option<&'q Query<'q>>,
+    rows: usize,
+    index: usize,
+    error: Option<&'static str>,
+}
+
+impl<'q> Insert<'q> {
+    /// Constructs a new, empty `Insert`.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            columns: Vec::new(),
+            values: String::new(),
+            params: Vec::new(),
+            select: None,
+            rows: 1,
+            index: 0,
+            error: None,
+        }
+    }
+
+    /// Constructs an `Insert` whose rows are selected by `query`, for an `INSERT ... SELECT`
+    /// query. The query must select values for `columns` in order.
+    pub fn select(columns: &'q [&'q str], query: &'q impl AsRef<Query<'q>>) -> Self {
+        Self {
+            columns: columns.to_vec(),
+            select: Some(query.as_ref()),
+            index: columns.len(),
+            ..Self::new()
+        }
+    }
+
+    /// Returns `true` if no column has been set.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Sets a column and its corresponding value in the current row.
+    pub fn set(mut self, column: &'q str, value: &'q (dyn Param<'q> + Sync)) -> Self {
+        self.add_column(column);
+        self.values.push('?');
+        self.params.push(value);
+        self
+    }
+
+    /// Sets a column to a raw SQL value in the current row.
+    pub fn set_raw(mut self, column: &'q str, value: &'q str) -> Self {
+        self.add_column(column);
+        self.values.push_str(value);
+        self
+    }
+
+    /// Starts a new row.
+    ///
+    /// The current row must set all the columns of the first row.
+    pub fn row(mut self) -> Self {
+        if self.select.is_some() || self.index != self.columns.len() || self.index == 0 {
+            self.fail("all the columns must be set before starting a new row");
+        }
+        self.values.push_str("),(");
+        self.rows += 1;
+        self.index = 0;
+        self
+    }
+
+    fn add_column(&mut self, column: &'q str) {
+        if self.select.is_some() {
+            return self.fail("cannot set values of an INSERT ... SELECT");
+        }
+        if self.index > 0 {
+            self.values.push(',');
+        }
+        if self.rows == 1 {
+            self.columns.push(column);
+        } else if self.columns.get(self.index) != Some(&column) {
+            self.fail("the columns must be set in the same order as in the first row");
+        }
+        self.index += 1;
+    }
+
+    /// Records the first misuse, returned when the query is executed.
+    fn fail(&mut self, error: &'static str) {
+        self.error.get_or_insert(error);
+    }
+
+    fn check(&self) -> crate::Result<()> {
+        if let Some(error) = self.error {
+            return Err(Error::InvalidInsert(error));
+        }
+        if self.index != self.columns.len() {
+            return Err(Error::InvalidInsert(
+                "all the columns must be set in the last row",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for Insert<'_> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// A query builder for constructing SELECT, INSERT, UPDATE, or DELETE queries.
+#[derive(Clone)]
 pub struct Query<'q> {
-    table: &'q str,
+    ctes: Vec<Cte<'q>>,
+    source: Source<'q>,
+    alias: Option<&'q str>,
     columns: Option<&'q [&'q str]>,
     select: Select<'q>,
-    criteria: Vec<(&'q str, &'q [&'q (dyn Param<'q> + Sync)])>,
+    joins: Vec<Join<'q>>,
+    criteria: Vec<Condition<'q>>,
     group_by: Select<'q>,
-    having: Vec<(&'q str, &'q [&'q (dyn Param<'q> + Sync)])>,
+    having: Vec<Condition<'q>>,
     order_by: Vec<OrderBy<'q>>,
     offset: Option<usize>,
     limit: Option<usize>,
+    compounds: Vec<(&'static str, SubQuery<'q>)>,
+    /// The DISTINCT ON columns, empty for a plain DISTINCT.
+    distinct: Option<Cow<'q, [&'q str]>>,
+    lock: Option<&'static str>,
+    lock_wait: Option<&'static str>,
 }
 
 impl<'q> Query<'q> {
     pub(crate) fn new(table: &'q str, columns: Option<&'q [&'q str]>) -> Self {
         Self {
-            table,
+            ctes: Vec::new(),
+            source: Source::Table(table),
+            alias: None,
             columns,
             select: Select::None,
+            joins: Vec::new(),
             criteria: Vec::new(),
             group_by: Select::None,
             having: Vec::new(),
             order_by: Vec::new(),
             offset: None,
             limit: None,
+            compounds: Vec::new(),
+            distinct: None,
+            lock: None,
+            lock_wait: None,
         }
     }
 
@@ -167,9 +599,199 @@ impl<'q> Query<'q> {
     ///
     /// This method initializes a new instance of the query builder for the given
     /// table name. It is typically used as the starting point for building a query.
+    ///
+    /// Pass a `(table, alias)` pair to give the table an alias.
+    #[inline]
+    pub fn table(table: impl Table<'q>) -> Self {
+        let mut query = Self::new(table.name(), None);
+        query.alias = table.alias();
+        query
+    }
+
+    /// Creates a new query builder selecting from a subquery, also known as a derived table.
+    ///
+    /// The parameters of the subquery are bound before those of the outer query.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Query;
+    /// # use sorm::clause;
+    /// let mut totals = Query::table("orders");
+    /// totals
+    ///     .select_raw("user_id, SUM(amount) AS total")
+    ///     .group_by(&["user_id"]);
+    /// let min = 100;
+    /// let mut query = Query::from_sub(&totals, "t");
+    /// query.r#where(clause!("t.total>{min}"));
+    /// // SELECT * FROM (SELECT user_id, SUM(amount) AS total FROM `orders` GROUP BY `user_id`) AS `t` WHERE t.total>?
+    /// ```
+    #[inline]
+    pub fn from_sub(query: &'q impl AsRef<Query<'q>>, alias: &'q str) -> Self {
+        let mut this = Self::new("", None);
+        this.source = Source::Sub(SubQuery::Borrowed(query.as_ref()));
+        this.alias = Some(alias);
+        this
+    }
+
+    /// Sets the alias of the table.
+    #[inline]
+    pub fn alias(&mut self, alias: &'q str) -> &mut Self {
+        self.alias = Some(alias);
+        self
+    }
+
+    /// Adds an INNER JOIN clause to the query builder.
+    ///
+    /// The `on` clause accepts parameters like [`Query::r#where`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Query;
+    /// # use sorm::clause;
+    /// let status = 1;
+    /// let mut query = Query::table(("users", "u"));
+    /// query
+    ///     .select(&["u.*", "o.amount"])
+    ///     .join(("orders", "o"), clause!("o.user_id=u.id AND o.status={status}"));
+    /// ```
+    #[inline]
+    pub fn join(&mut self, table: impl Table<'q>, on: impl Clause<'q>) -> &mut Self {
+        self.add_join("INNER JOIN", table, Some((on.expr(), on.params().into())))
+    }
+
+    /// Adds a LEFT JOIN clause to the query builder.
+    ///
+    /// See [`Query::join`].
+    #[inline]
+    pub fn left_join(&mut self, table: impl Table<'q>, on: impl Clause<'q>) -> &mut Self {
+        self.add_join("LEFT JOIN", table, Some((on.expr(), on.params().into())))
+    }
+
+    /// Adds a RIGHT JOIN clause to the query builder.
+    ///
+    /// See [`Query::join`].
+    #[inline]
+    pub fn right_join(&mut self, table: impl Table<'q>, on: impl Clause<'q>) -> &mut Self {
+        self.add_join("RIGHT JOIN", table, Some((on.expr(), on.params().into())))
+    }
+
+    /// Adds a CROSS JOIN clause to the query builder.
+    #[inline]
+    pub fn cross_join(&mut self, table: impl Table<'q>) -> &mut Self {
+        self.add_join("CROSS JOIN", table, None)
+    }
+
+    fn add_join(
+        &mut self,
+        kind: &'static str,
+        table: impl Table<'q>,
+        on: Option<(&'q str, Params<'q>)>,
+    ) -> &mut Self {
+        self.joins.push(Join {
+            kind,
+            table: table.name(),
+            alias: table.alias(),
+            on,
+        });
+        self
+    }
+
+    /// Adds a common table expression named `name` to the WITH clause of the query, which can
+    /// be used as a table by the query.
+    ///
+    /// Parameters of the common table expressions are bound first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Query;
+    /// let mut totals = Query::table("orders");
+    /// totals
+    ///     .select_raw("user_id,SUM(amount) AS total")
+    ///     .group_by(&["user_id"]);
+    /// let mut query = Query::table("totals");
+    /// query.with("totals", &totals).r#where("total>100");
+    /// // WITH `totals` AS (SELECT user_id,SUM(amount) AS total FROM `orders` GROUP BY `user_id`)
+    /// // SELECT * FROM `totals` WHERE total>100
+    /// ```
+    #[inline]
+    pub fn with(&mut self, name: &'q str, query: &'q impl AsRef<Query<'q>>) -> &mut Self {
+        self.ctes.push(Cte {
+            name,
+            query: SubQuery::Borrowed(query.as_ref()),
+            recursive: None,
+        });
+        self
+    }
+
+    /// Adds a recursive common table expression named `name` to the WITH clause of the query,
+    /// combining the `anchor` query with the `recursive` query, which refers to `name`, using
+    /// UNION ALL.
+    ///
+    /// See [`Query::with`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Query;
+    /// let mut anchor = Query::table("categories");
+    /// anchor.select(&["id", "parent_id"]).r#where("id=1");
+    /// let mut recursive = Query::table(("categories", "c"));
+    /// recursive
+    ///     .select(&["c.id", "c.parent_id"])
+    ///     .join(("tree", "t"), "c.parent_id=t.id");
+    /// let mut query = Query::table("tree");
+    /// query.with_recursive("tree", &anchor, &recursive);
+    /// // WITH RECURSIVE `tree` AS (SELECT `id`,`parent_id` FROM `categories` WHERE id=1
+    /// // UNION ALL SELECT `c`.`id`,`c`.`parent_id` FROM `categories` AS `c`
+    /// // INNER JOIN `tree` AS `t` ON c.parent_id=t.id) SELECT * FROM `tree`
+    /// ```
+    #[inline]
+    pub fn with_recursive(
+        &mut self,
+        name: &'q str,
+        anchor: &'q impl AsRef<Query<'q>>,
+        recursive: &'q impl AsRef<Query<'q>>,
+    ) -> &mut Self {
+        self.ctes.push(Cte {
+            name,
+            query: SubQuery::Borrowed(anchor.as_ref()),
+            recursive: Some(SubQuery::Borrowed(recursive.as_ref())),
+        });
+        self
+    }
+
+    /// Selects only distinct rows with the DISTINCT keyword.
+    #[inline]
+    pub fn distinct(&mut self) -> &mut Self {
+        self.distinct = Some(Cow::Borrowed(&[]));
+        self
+    }
+
+    /// Selects only the first row of each set of rows having the same values for `columns`,
+    /// with the `DISTINCT ON` clause of PostgreSQL.
+    ///
+    /// The first row of each set is unpredictable unless the query is ordered by `columns`
+    /// first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Query;
+    /// let mut query = Query::table("orders");
+    /// query
+    ///     .distinct_on(&["user_id"])
+    ///     .order_by("user_id")
+    ///     .order_by_desc("created_at");
+    /// // SELECT DISTINCT ON ("user_id") * FROM "orders" ORDER BY "user_id","created_at" DESC
+    /// ```
+    #[cfg(feature = "postgres")]
     #[inline]
-    pub fn table(table: &'q str) -> Self {
-        Self::new(table, None)
+    pub fn distinct_on(&mut self, columns: &'q [&'q str]) -> &mut Self {
+        self.distinct = Some(Cow::Borrowed(columns));
+        self
     }
 
     /// Sets the columns to be selected in the query.
@@ -180,7 +802,7 @@ impl<'q> Query<'q> {
     /// previously, it will also be overwritten by this method.
     #[inline]
     pub fn select(&mut self, columns: &'q [&str]) -> &mut Self {
-        self.select = Select::Columns(columns);
+        self.select = Select::Columns(Cow::Borrowed(columns));
         self
     }
 
@@ -190,9 +812,24 @@ impl<'q> Query<'q> {
     /// It can only be called once. If called multiple times, the last
     /// call will overwrite the previous one. If `select` has been called
     /// previously, it will also be overwritten by this method.
+    ///
+    /// The expression can be a [`Clause`] carrying parameters, which are bound before the
+    /// parameters of the other clauses, following the order of the SQL.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::clause;
+    /// # use sorm::query::Query;
+    /// let fallback = "unknown";
+    /// let params;
+    /// let mut query = Query::table("users");
+    /// query.select_raw(clause!("id,COALESCE(name,{&fallback})", params));
+    /// // SELECT id,COALESCE(name,?) FROM `users`
+    /// ```
     #[inline]
-    pub fn select_raw(&mut self, expr: &'q str) -> &mut Self {
-        self.select = Select::Raw(expr);
+    pub fn select_raw(&mut self, expr: impl Clause<'q>) -> &mut Self {
+        self.select = Select::Raw(expr.expr(), expr.params().into());
         self
     }
 
@@ -230,35 +867,135 @@ impl<'q> Query<'q> {
     /// query.r#where(clause!("name={&name} AND enable={enable}"));
     /// ```
     pub fn r#where(&mut self, clause: impl Clause<'q>) -> &mut Self {
-        if !self.criteria.is_empty() {
-            self.criteria.push((" AND ", &[]));
-        }
-        self.criteria.push((clause.expr(), clause.params()));
+        add_condition(
+            &mut self.criteria,
+            Condition::And,
+            Condition::Clause(clause.expr(), clause.params().into()),
+        );
         self
     }
 
+    /// Adds a WHERE clause with owned parameters.
+    pub(crate) fn where_params(&mut self, expr: &'q str, params: Vec<&'q (dyn Param<'q> + Sync)>) {
+        add_condition(
+            &mut self.criteria,
+            Condition::And,
+            Condition::Clause(expr, params.into()),
+        );
+    }
+
     /// Adds an OR WHERE clause to the query builder.
     ///
     /// See [`Query:: where `] for the usage of the `clause` argument.
     pub fn or_where(&mut self, clause: impl Clause<'q>) -> &mut Self {
-        if !self.criteria.is_empty() {
-            self.criteria.push((" OR ", &[]));
-        }
-        self.criteria.push((clause.expr(), clause.params()));
+        add_condition(
+            &mut self.criteria,
+            Condition::Or,
+            Condition::Clause(clause.expr(), clause.params().into()),
+        );
         self
     }
 
-    /// Specifies the GROUP BY clause for the query builder.
+    /// Adds a group of WHERE clauses wrapped in parentheses to the query builder.
     ///
-    /// This method sets the GROUP BY clause of the query to group the results by the specified fields.
-    /// It can only be called once. If called multiple times, the last
-    /// call will overwrite the previous one. If `group_by_raw` has been called
-    /// previously, it will also be overwritten by this method.
-    #[inline]
-    pub fn group_by(&mut self, fields: &'q [&str]) -> &mut Self {
-        self.group_by = Select::Columns(fields);
-        self
-    }
+    /// The clauses added to the query passed to `f` form the group, which is combined with the
+    /// existing criteria using the "AND" operator. An empty group is ignored.
+    ///
+    /// Clauses with parameters must outlive the query, so create them outside of `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Query;
+    /// # use sorm::clause;
+    /// let role = "admin";
+    /// let by_role = clause!("role={&role}");
+    /// let mut query = Query::table("users");
+    /// query
+    ///     .r#where("enable=1")
+    ///     .where_group(|q| {
+    ///         q.r#where(by_role).or_where("role IS NULL");
+    ///     });
+    /// // SELECT * FROM `users` WHERE (enable=1) AND ((role=?) OR (role IS NULL))
+    /// ```
+    pub fn where_group(&mut self, f: impl FnOnce(&mut Query<'q>)) -> &mut Self {
+        let mut query = Query::new("", None);
+        f(&mut query);
+        add_group(&mut self.criteria, Condition::And, query.criteria);
+        self
+    }
+
+    /// Adds a group of WHERE clauses wrapped in parentheses to the query builder,
+    /// combined with the existing criteria using the "OR" operator.
+    ///
+    /// See [`Query::where_group`].
+    pub fn or_where_group(&mut self, f: impl FnOnce(&mut Query<'q>)) -> &mut Self {
+        let mut query = Query::new("", None);
+        f(&mut query);
+        add_group(&mut self.criteria, Condition::Or, query.criteria);
+        self
+    }
+
+    /// Adds a `column IN (subquery)` WHERE clause to the query builder.
+    ///
+    /// The clause is combined with the existing criteria using the "AND" operator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Query;
+    /// # use sorm::clause;
+    /// let status = 1;
+    /// let params;
+    /// let mut orders = Query::table("orders");
+    /// orders.select(&["user_id"]).r#where(clause!("status={status}", params));
+    /// let mut query = Query::table("users");
+    /// query.where_in_sub("id", &orders);
+    /// // SELECT * FROM `users` WHERE `id` IN (SELECT `user_id` FROM `orders` WHERE status=?)
+    /// ```
+    pub fn where_in_sub(&mut self, column: &'q str, query: &'q impl AsRef<Query<'q>>) -> &mut Self {
+        add_condition(
+            &mut self.criteria,
+            Condition::And,
+            Condition::In(column, SubQuery::Borrowed(query.as_ref())),
+        );
+        self
+    }
+
+    /// Adds an `EXISTS (subquery)` WHERE clause to the query builder.
+    ///
+    /// The clause is combined with the existing criteria using the "AND" operator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Query;
+    /// let mut orders = Query::table("orders");
+    /// orders.r#where("orders.user_id=users.id");
+    /// let mut query = Query::table("users");
+    /// query.where_exists(&orders);
+    /// // SELECT * FROM `users` WHERE EXISTS (SELECT * FROM `orders` WHERE orders.user_id=users.id)
+    /// ```
+    pub fn where_exists(&mut self, query: &'q impl AsRef<Query<'q>>) -> &mut Self {
+        add_condition(
+            &mut self.criteria,
+            Condition::And,
+            Condition::Exists(SubQuery::Borrowed(query.as_ref())),
+        );
+        self
+    }
+
+    /// Specifies the GROUP BY clause for the query builder.
+    ///
+    /// This method sets the GROUP BY clause of the query to group the results by the specified fields.
+    /// It can only be called once. If called multiple times, the last
+    /// call will overwrite the previous one. If `group_by_raw` has been called
+    /// previously, it will also be overwritten by this method.
+    #[inline]
+    pub fn group_by(&mut self, fields: &'q [&str]) -> &mut Self {
+        self.group_by = Select::Columns(Cow::Borrowed(fields));
+        self
+    }
 
     /// Specifies a raw SQL expression for the GROUP BY clause in the query builder.
     ///
@@ -266,9 +1003,11 @@ impl<'q> Query<'q> {
     /// It can only be called once. If called multiple times, the last
     /// call will overwrite the previous one. If `group_by` has been called previously,
     /// it will also be overwritten by this method.
+    ///
+    /// The expression can be a [`Clause`] carrying parameters, see [`Query::select_raw`].
     #[inline]
-    pub fn group_by_raw(&mut self, expr: &'q str) -> &mut Self {
-        self.group_by = Select::Raw(expr);
+    pub fn group_by_raw(&mut self, expr: impl Clause<'q>) -> &mut Self {
+        self.group_by = Select::Raw(expr.expr(), expr.params().into());
         self
     }
 
@@ -276,10 +1015,11 @@ impl<'q> Query<'q> {
     ///
     /// See [`Query:: where `] for the usage of the `clause` argument.
     pub fn having(&mut self, clause: impl Clause<'q>) -> &mut Self {
-        if !self.having.is_empty() {
-            self.having.push((" AND ", &[]));
-        }
-        self.having.push((clause.expr(), clause.params()));
+        add_condition(
+            &mut self.having,
+            Condition::And,
+            Condition::Clause(clause.expr(), clause.params().into()),
+        );
         self
     }
 
@@ -287,10 +1027,33 @@ impl<'q> Query<'q> {
     ///
     /// See [`Query:: where `] for the usage of the `clause` argument.
     pub fn or_having(&mut self, clause: impl Clause<'q>) -> &mut Self {
-        if !self.having.is_empty() {
-            self.having.push((" OR ", &[]));
-        }
-        self.having.push((clause.expr(), clause.params()));
+        add_condition(
+            &mut self.having,
+            Condition::Or,
+            Condition::Clause(clause.expr(), clause.params().into()),
+        );
+        self
+    }
+
+    /// Adds a group of HAVING clauses wrapped in parentheses to the query builder.
+    ///
+    /// The clauses added to the query passed to `f` with [`Query::having`] and
+    /// [`Query::or_having`] form the group. See [`Query::where_group`].
+    pub fn having_group(&mut self, f: impl FnOnce(&mut Query<'q>)) -> &mut Self {
+        let mut query = Query::new("", None);
+        f(&mut query);
+        add_group(&mut self.having, Condition::And, query.having);
+        self
+    }
+
+    /// Adds a group of HAVING clauses wrapped in parentheses to the query builder,
+    /// combined with the existing ones using the "OR" operator.
+    ///
+    /// See [`Query::having_group`].
+    pub fn or_having_group(&mut self, f: impl FnOnce(&mut Query<'q>)) -> &mut Self {
+        let mut query = Query::new("", None);
+        f(&mut query);
+        add_group(&mut self.having, Condition::Or, query.having);
         self
     }
 
@@ -309,12 +1072,64 @@ impl<'q> Query<'q> {
     }
 
     /// Adds a raw ORDER BY clause to the query builder.
+    ///
+    /// The expression can be a [`Clause`] carrying parameters, see [`Query::select_raw`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::clause;
+    /// # use sorm::query::Query;
+    /// let name = "foo";
+    /// let params;
+    /// let mut query = Query::table("users");
+    /// query.order_by_raw(clause!("similarity(name,{&name}) DESC", params));
+    /// // SELECT * FROM `users` ORDER BY similarity(name,?) DESC
+    /// ```
     #[inline]
-    pub fn order_by_raw(&mut self, order_by: &'q str) -> &mut Self {
-        self.order_by.push(OrderBy::Raw(order_by));
+    pub fn order_by_raw(&mut self, order_by: impl Clause<'q>) -> &mut Self {
+        self.order_by
+            .push(OrderBy::Raw(order_by.expr(), order_by.params().into()));
         self
     }
 
+    /// Adds ORDER BY clauses parsed from user input, like the sort parameter of an API.
+    ///
+    /// The input is a comma separated list of columns, each prefixed with `-` for the descending
+    /// order and optionally suffixed with `:nulls_first` or `:nulls_last`, the whitespace around
+    /// the entries is ignored. Every column must be one of `allowed`, like the
+    /// [`crate::model::Model::COLUMNS`] of a model, otherwise [`Error::InvalidOrderBy`] is
+    /// returned and no clause is added.
+    ///
+    /// MySQL has no `NULLS FIRST` and `NULLS LAST`, they are emulated by ordering by
+    /// `column IS NULL` first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Query;
+    /// let mut query = Query::table("users");
+    /// query
+    ///     .order_by_checked("-created_at:nulls_last,name", &["name", "created_at"])
+    ///     .unwrap();
+    /// // SELECT * FROM `users` ORDER BY `created_at` DESC NULLS LAST,`name`
+    /// assert!(query.order_by_checked("password", &["name"]).is_err());
+    /// ```
+    pub fn order_by_checked(
+        &mut self,
+        input: &'q str,
+        allowed: &[&str],
+    ) -> crate::Result<&mut Self> {
+        let fields = parse_sort_checked(input, allowed)?;
+        self.order_by
+            .extend(fields.into_iter().map(|(column, desc, nulls)| match nulls {
+                Some(nulls) => OrderBy::Nulls(column, desc, nulls),
+                None if desc => OrderBy::Desc(column),
+                None => OrderBy::Asc(column),
+            }));
+        Ok(self)
+    }
+
     /// Sets the OFFSET clause for the query builder.
     #[inline]
     pub fn offset(&mut self, offset: usize) -> &mut Self {
@@ -329,6 +1144,113 @@ impl<'q> Query<'q> {
         self
     }
 
+    /// Locks the selected rows for update with the `FOR UPDATE` clause.
+    ///
+    /// The lock is held until the end of the transaction. SQLite has no row locks, so the
+    /// locking clauses are ignored. The lock is only written when the query runs as a statement,
+    /// it is left out when the query is a compound member, a CTE or a subquery of another query.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Query;
+    /// let mut query = Query::table("jobs");
+    /// query
+    ///     .r#where("status=0")
+    ///     .order_by("id")
+    ///     .limit(10)
+    ///     .lock_for_update()
+    ///     .skip_locked();
+    /// // SELECT * FROM `jobs` WHERE status=0 ORDER BY `id` LIMIT 10 FOR UPDATE SKIP LOCKED
+    /// ```
+    #[inline]
+    pub fn lock_for_update(&mut self) -> &mut Self {
+        self.lock = Some(" FOR UPDATE");
+        self
+    }
+
+    /// Locks the selected rows against updates with the `FOR SHARE` clause.
+    ///
+    /// See [`Query::lock_for_update`].
+    #[inline]
+    pub fn lock_for_share(&mut self) -> &mut Self {
+        self.lock = Some(" FOR SHARE");
+        self
+    }
+
+    /// Skips the rows that are already locked instead of waiting, for queries locked with
+    /// [`Query::lock_for_update`] or [`Query::lock_for_share`].
+    #[inline]
+    pub fn skip_locked(&mut self) -> &mut Self {
+        self.lock_wait = Some(" SKIP LOCKED");
+        self
+    }
+
+    /// Fails instead of waiting when a row is already locked, for queries locked with
+    /// [`Query::lock_for_update`] or [`Query::lock_for_share`].
+    #[inline]
+    pub fn nowait(&mut self) -> &mut Self {
+        self.lock_wait = Some(" NOWAIT");
+        self
+    }
+
+    /// Combines the query with another one using the UNION operator.
+    ///
+    /// The ORDER BY, LIMIT and OFFSET clauses of this query apply to the combined result,
+    /// while those of `query` only apply to itself. Parameters are bound in the order
+    /// the queries are combined.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Query;
+    /// let mut archive = Query::table("archived_orders");
+    /// archive.select(&["id", "amount"]);
+    /// let mut query = Query::table("orders");
+    /// query
+    ///     .select(&["id", "amount"])
+    ///     .union(&archive)
+    ///     .order_by_desc("amount")
+    ///     .limit(10);
+    /// // SELECT `id`,`amount` FROM `orders` UNION SELECT `id`,`amount` FROM `archived_orders` ORDER BY `amount` DESC LIMIT 10
+    /// ```
+    #[inline]
+    pub fn union(&mut self, query: &'q impl AsRef<Query<'q>>) -> &mut Self {
+        self.compounds
+            .push((" UNION ", SubQuery::Borrowed(query.as_ref())));
+        self
+    }
+
+    /// Combines the query with another one using the UNION ALL operator.
+    ///
+    /// See [`Query::union`].
+    #[inline]
+    pub fn union_all(&mut self, query: &'q impl AsRef<Query<'q>>) -> &mut Self {
+        self.compounds
+            .push((" UNION ALL ", SubQuery::Borrowed(query.as_ref())));
+        self
+    }
+
+    /// Combines the query with another one using the INTERSECT operator.
+    ///
+    /// See [`Query::union`].
+    #[inline]
+    pub fn intersect(&mut self, query: &'q impl AsRef<Query<'q>>) -> &mut Self {
+        self.compounds
+            .push((" INTERSECT ", SubQuery::Borrowed(query.as_ref())));
+        self
+    }
+
+    /// Combines the query with another one using the EXCEPT operator.
+    ///
+    /// See [`Query::union`].
+    #[inline]
+    pub fn except(&mut self, query: &'q impl AsRef<Query<'q>>) -> &mut Self {
+        self.compounds
+            .push((" EXCEPT ", SubQuery::Borrowed(query.as_ref())));
+        self
+    }
+
     /// Fetch a given column
     ///
     /// If more than one column is given, the first column is used.
@@ -362,6 +1284,26 @@ impl<'q> Query<'q> {
         Ok(list)
     }
 
+    /// Fetch a given column as a stream.
+    ///
+    /// If more than one column is given, the first column is used.
+    /// See [`Query::fetch`].
+    pub fn fetch_plunk<'e, T>(
+        &'e self,
+        executor: impl Executor<'e, Database = Database> + 'e,
+    ) -> BoxStream<'e, crate::Result<T>>
+    where
+        T: for<'r> Decode<'r, Database> + Type<Database> + Send + 'e,
+    {
+        Box::pin(try_stream! {
+            let (sql, params) = self.build_select(None);
+            let rows = sqlx::query_with(&sql, to_args(params)?).fetch(executor);
+            for await row in rows {
+                yield row?.try_get::<T, _>(0)?;
+            }
+        })
+    }
+
     /// Fetch a single value for a given column.
     ///
     /// If more than one column is given, the first column is used.
@@ -459,6 +1401,46 @@ impl<'q> Query<'q> {
             .await?)
     }
 
+    /// Executes a SELECT query and streams the results mapped to items of type `T`.
+    ///
+    /// Unlike [`Query::get`], the rows are decoded one by one as they are received.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use futures_core::stream::BoxStream;
+    /// # use sqlx::FromRow;
+    /// # use sorm::query::Query;
+    /// # use sorm::{Database, Result};
+    /// #[derive(FromRow)]
+    /// struct User {
+    ///     id: i64,
+    ///     name: String,
+    /// }
+    ///
+    /// fn export_user<'e>(
+    ///     db: impl sqlx::Executor<'e, Database = Database> + 'e,
+    ///     query: &'e Query<'e>,
+    /// ) -> BoxStream<'e, Result<User>> {
+    ///     query.fetch(db)
+    /// }
+    /// ```
+    pub fn fetch<'e, T>(
+        &'e self,
+        executor: impl Executor<'e, Database = Database> + 'e,
+    ) -> BoxStream<'e, crate::Result<T>>
+    where
+        T: for<'r> FromRow<'r, crate::Row> + Send + Unpin + 'e,
+    {
+        Box::pin(try_stream! {
+            let (sql, params) = self.build_select(None);
+            let rows = sqlx::query_as_with(&sql, to_args(params)?).fetch(executor);
+            for await row in rows {
+                yield row?;
+            }
+        })
+    }
+
     /// Executes a SELECT query and fetches a single result mapped to item of type `T`.
     ///
     /// # Examples
@@ -527,136 +1509,1024 @@ impl<'q> Query<'q> {
             .await?)
     }
 
-    fn build_select(&self, limit: Option<usize>) -> (String, Vec<&'q (dyn Param<'q> + Sync)>) {
-        let (expr_len, param_count) = self.criteria_size();
-        let mut sql = String::with_capacity(64 + expr_len);
-        let mut params = Vec::with_capacity(param_count);
+    /// Counts the rows of the query.
+    ///
+    /// The selected columns and the ORDER BY, LIMIT and OFFSET clauses are ignored. Queries with
+    /// DISTINCT, GROUP BY or HAVING clauses or set operations are counted through a subquery,
+    /// so the distinct rows or the groups are counted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Query;
+    /// # use sorm::{Database, Result};
+    /// async fn count_user(db: impl sqlx::Executor<'_, Database = Database>) -> Result<u64> {
+    ///     Query::table("users").r#where("age>18").count(db).await
+    ///     // SELECT COUNT(*) FROM `users` WHERE age>18
+    /// }
+    /// ```
+    pub async fn count(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+    ) -> crate::Result<u64> {
+        let count: i64 = self.aggregate(executor, "COUNT(*)").await?;
+        Ok(count as u64)
+    }
 
-        sql.push_str("SELECT ");
-        match self.select {
-            Select::Columns(fields) => concat_idents(&mut sql, fields),
-            Select::Raw(expr) => sql.push_str(expr),
-            Select::Omitted(ref omit) => {
-                for v in self.columns.unwrap() {
-                    if !omit.contains(v) {
-                        concat_ident(&mut sql, v);
-                    }
+    /// Returns whether the query has any row.
+    ///
+    /// The selected columns are ignored unless the query has DISTINCT, GROUP BY or HAVING
+    /// clauses or set operations.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Query;
+    /// # use sorm::{Database, clause, Result};
+    /// async fn has_user(
+    ///     db: impl sqlx::Executor<'_, Database = Database>,
+    ///     name: &str,
+    /// ) -> Result<bool> {
+    ///     Query::table("users").r#where(clause!("name={&name}")).exists(db).await
+    ///     // SELECT EXISTS(SELECT 1 FROM `users` WHERE name=?)
+    /// }
+    /// ```
+    pub async fn exists(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+    ) -> crate::Result<bool> {
+        let (sql, params) = self.build(|sql, params| {
+            self.write_with(sql, params);
+            sql.push_str("SELECT EXISTS(");
+            match self.is_grouped() {
+                true => self.write_select_body(sql, params),
+                false => {
+                    sql.push_str("SELECT 1");
+                    self.write_from(sql, params, None);
                 }
             }
-            Select::None => match self.columns {
-                Some(columns) => concat_idents(&mut sql, columns),
-                None => sql.push_str("*"),
-            },
-        }
+            sql.push(')');
+        });
+        let row = sqlx::query_with(&sql, to_args(params)?)
+            .fetch_one(executor)
+            .await?;
+        Ok(row.try_get(0)?)
+    }
 
-        sql.push_str(" FROM ");
-        concat_ident(&mut sql, self.table);
+    /// Sums the values of `column`, returning 0 if there are no rows.
+    ///
+    /// The aggregate is computed like in [`Query::count`], for queries with DISTINCT, GROUP BY
+    /// or HAVING clauses or set operations `column` must be a column selected by the query.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::Query;
+    /// # use sorm::{Database, Result};
+    /// async fn total_amount(db: impl sqlx::Executor<'_, Database = Database>) -> Result<i64> {
+    ///     Query::table("orders").sum(db, "amount").await
+    ///     // SQLite: SELECT CAST(COALESCE(SUM(`amount`),0) AS INTEGER) FROM `orders`
+    /// }
+    /// ```
+    pub async fn sum<T: Number>(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+        column: &str,
+    ) -> crate::Result<T> {
+        let mut expr = String::with_capacity(column.len() + 48);
+        expr.push_str("CAST(COALESCE(SUM(");
+        concat_ident(&mut expr, column);
+        expr.push_str("),0) AS ");
+        expr.push_str(T::SQL_TYPE);
+        expr.push(')');
+        self.aggregate(executor, &expr).await
+    }
 
-        if !self.criteria.is_empty() {
-            sql.push_str(" WHERE ");
-            for v in &self.criteria {
-                sql.push_str(v.expr());
-                params.extend_from_slice(v.params());
-            }
-        }
+    /// Averages the values of `column`, returning `None` if there are no rows.
+    ///
+    /// See [`Query::sum`].
+    pub async fn avg<T: Number>(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+        column: &str,
+    ) -> crate::Result<Option<T>> {
+        let mut expr = String::with_capacity(column.len() + 32);
+        expr.push_str("CAST(AVG(");
+        concat_ident(&mut expr, column);
+        expr.push_str(") AS ");
+        expr.push_str(T::SQL_TYPE);
+        expr.push(')');
+        self.aggregate(executor, &expr).await
+    }
 
-        match self.group_by {
-            Select::Columns(fields) => {
-                sql.push_str(" GROUP BY ");
-                concat_idents(&mut sql, fields);
-            }
-            Select::Raw(expr) => {
-                sql.push_str(" GROUP BY ");
-                sql.push_str(expr);
-            }
-            Select::Omitted(_) => unreachable!(),
-            Select::None => (),
-        }
+    /// Returns the minimum value of `column`, or `None` if there are no rows.
+    ///
+    /// The value has the type of the column. See [`Query::sum`].
+    pub async fn min<T>(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+        column: &str,
+    ) -> crate::Result<Option<T>>
+    where
+        T: for<'r> Decode<'r, Database> + Type<Database>,
+    {
+        let mut expr = String::with_capacity(column.len() + 7);
+        expr.push_str("MIN(");
+        concat_ident(&mut expr, column);
+        expr.push(')');
+        self.aggregate(executor, &expr).await
+    }
 
-        if !self.having.is_empty() {
-            sql.push_str(" HAVING ");
-            for v in &self.having {
-                sql.push_str(v.expr());
-                params.extend_from_slice(v.params());
-            }
-        }
+    /// Returns the maximum value of `column`, or `None` if there are no rows.
+    ///
+    /// The value has the type of the column. See [`Query::sum`].
+    pub async fn max<T>(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+        column: &str,
+    ) -> crate::Result<Option<T>>
+    where
+        T: for<'r> Decode<'r, Database> + Type<Database>,
+    {
+        let mut expr = String::with_capacity(column.len() + 7);
+        expr.push_str("MAX(");
+        concat_ident(&mut expr, column);
+        expr.push(')');
+        self.aggregate(executor, &expr).await
+    }
 
-        if !self.order_by.is_empty() {
-            sql.push_str(" ORDER BY ");
-            for v in &self.order_by {
-                match v {
-                    OrderBy::Asc(v) => concat_ident(&mut sql, v),
-                    OrderBy::Desc(v) => {
-                        concat_ident(&mut sql, v);
-                        sql.push_str(" DESC");
-                    }
-                    OrderBy::Raw(v) => sql.push_str(v),
-                }
-                sql.push_str(",");
+    async fn aggregate<T>(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+        expr: &str,
+    ) -> crate::Result<T>
+    where
+        T: for<'r> Decode<'r, Database> + Type<Database>,
+    {
+        let (sql, params) = self.build(|sql, params| self.write_aggregate(sql, params, expr));
+        let row = sqlx::query_with(&sql, to_args(params)?)
+            .fetch_one(executor)
+            .await?;
+        Ok(row.try_get(0)?)
+    }
+
+    /// Executes a SELECT query for the given page along with a query counting all the rows.
+    ///
+    /// Pages start at 1. The count query shares the criteria of the query but ignores its
+    /// ORDER BY, LIMIT and OFFSET clauses, and queries with DISTINCT, GROUP BY or HAVING
+    /// clauses or set operations are counted through a subquery.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sqlx::FromRow;
+    /// # use sorm::query::{Page, Query};
+    /// # use sorm::{Database, Result};
+    /// #[derive(FromRow)]
+    /// struct User {
+    ///     id: i64,
+    ///     name: String,
+    /// }
+    ///
+    /// async fn list_user(
+    ///     db: impl sqlx::Acquire<'_, Database = Database>,
+    ///     page: u64,
+    /// ) -> Result<Page<User>> {
+    ///     Query::table("users").order_by_desc("id").paginate(db, page, 20).await
+    /// }
+    /// ```
+    pub async fn paginate<'c, T>(
+        &self,
+        executor: impl Acquire<'c, Database = Database>,
+        page: u64,
+        per_page: u64,
+    ) -> crate::Result<Page<T>>
+    where
+        T: for<'r> FromRow<'r, crate::Row> + Send + Unpin,
+    {
+        let page = page.max(1);
+        let per_page = per_page.max(1);
+        let mut conn = executor.acquire().await?;
+
+        let (sql, params) = self.build(|sql, params| self.write_aggregate(sql, params, "COUNT(*)"));
+        let total: i64 = sqlx::query_with(&sql, to_args(params)?)
+            .fetch_one(&mut *conn)
+            .await?
+            .try_get(0)?;
+        let total = total as u64;
+
+        // The pages past the last one are empty, including those whose offset overflows.
+        let offset = (page - 1).checked_mul(per_page).filter(|v| *v < total);
+        let items = match offset {
+            Some(offset) => {
+                // LIMIT and OFFSET are signed 64-bit integers in SQL.
+                let limit = usize::try_from(per_page.min(i64::MAX as u64)).unwrap_or(usize::MAX);
+                let offset = usize::try_from(offset).unwrap_or(usize::MAX);
+                let (sql, params) = self.build(|sql, params| {
+                    self.write_with(sql, params);
+                    self.write_select_body(sql, params);
+                    self.write_order_limit(sql, params, Some(limit), Some(offset));
+                    self.write_lock(sql);
+                });
+                sqlx::query_as_with(&sql, to_args(params)?)
+                    .fetch_all(&mut *conn)
+                    .await?
+            }
+            None => Vec::new(),
+        };
+
+        Ok(Page {
+            items,
+            total,
+            page,
+            per_page,
+            last_page: total.div_ceil(per_page).max(1),
+        })
+    }
+
+    /// Executes a SELECT query for the page at the given cursor, using keyset pagination.
+    ///
+    /// The query must be ordered by columns set with [`Query::order_by`] and
+    /// [`Query::order_by_desc`], which must be selected and together identify a row.
+    /// Pass `None` as the cursor to fetch the first page, then the `next_cursor` or
+    /// `prev_cursor` of the returned page to move forward or backward.
+    ///
+    /// The cursor condition is added to the WHERE clause, so queries with GROUP BY clauses must be
+    /// ordered by grouped columns, and the ordering columns must not be NULL: a NULL value in a
+    /// row of the page is rejected with [`Error::NullCursorColumn`].
+    ///
+    /// The ordering columns must be integers, floats, booleans, text or binary. Other types such
+    /// as timestamps, numerics and UUIDs cannot be stored in a cursor and are rejected with
+    /// [`Error::UnsupportedCursorColumn`] when the page is read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sqlx::FromRow;
+    /// # use sorm::query::{CursorPage, Query};
+    /// # use sorm::{Database, Result};
+    /// #[derive(FromRow)]
+    /// struct User {
+    ///     id: i64,
+    ///     name: String,
+    /// }
+    ///
+    /// async fn list_user(
+    ///     db: impl sqlx::Executor<'_, Database = Database>,
+    ///     cursor: Option<&str>,
+    /// ) -> Result<CursorPage<User>> {
+    ///     Query::table("users")
+    ///         .order_by("name")
+    ///         .order_by_desc("id")
+    ///         .cursor_paginate(db, cursor, 20)
+    ///         .await
+    ///     // SELECT * FROM `users` WHERE (`name`>? OR (`name`=? AND `id`<?)) ORDER BY `name`,`id` DESC LIMIT 21
+    /// }
+    /// ```
+    pub async fn cursor_paginate<T>(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+        cursor: Option<&str>,
+        per_page: u64,
+    ) -> crate::Result<CursorPage<T>>
+    where
+        T: for<'r> FromRow<'r, crate::Row> + Send + Unpin,
+    {
+        let per_page =
+            usize::try_from(per_page.clamp(1, i64::MAX as u64 - 1)).unwrap_or(usize::MAX - 1);
+        let mut columns = Vec::with_capacity(self.order_by.len());
+        for v in &self.order_by {
+            match *v {
+                OrderBy::Asc(v) => columns.push((v, false)),
+                OrderBy::Desc(v) => columns.push((v, true)),
+                OrderBy::Nulls(..) | OrderBy::Raw(..) => return Err(Error::NoOrderBy),
             }
-            sql.pop();
         }
+        if columns.is_empty() {
+            return Err(Error::NoOrderBy);
+        }
+        let cursor = cursor.map(Cursor::decode).transpose()?;
+        let before = match cursor {
+            Some(ref cursor) if cursor.values.len() != columns.len() => {
+                return Err(Error::InvalidCursor)
+            }
+            Some(ref cursor) => cursor.before,
+            None => false,
+        };
 
-        if let Some(limit) = limit.or(self.limit) {
-            sql.push_str(&format!(" LIMIT {}", limit))
+        let values = cursor.as_ref().map(|v| v.values.as_slice());
+        let (sql, params, at, values) = self.build_keyset(&columns, values, before, per_page + 1);
+        let mut rows = sqlx::query_with(&sql, to_args_with(params, at, &values)?)
+            .fetch_all(executor)
+            .await?;
+
+        let has_more = rows.len() > per_page;
+        rows.truncate(per_page);
+        if before {
+            rows.reverse();
         }
-        if let Some(offset) = self.offset {
-            sql.push_str(&format!(" OFFSET {}", offset))
+        let keys = |row: Option<&crate::Row>, before| -> crate::Result<Option<String>> {
+            let Some(row) = row else {
+                return Ok(None);
+            };
+            let mut values = Vec::with_capacity(columns.len());
+            for (column, _) in &columns {
+                let name = column.rsplit('.').next().unwrap_or(column);
+                values.push(Value::read(row, name)?);
+            }
+            Ok(Some(Cursor { before, values }.encode()))
+        };
+        let (has_prev, has_next) = match before {
+            true => (has_more, cursor.is_some()),
+            false => (cursor.is_some(), has_more),
+        };
+        let prev_cursor = match has_prev {
+            true => keys(rows.first(), true)?,
+            false => None,
+        };
+        let next_cursor = match has_next {
+            true => keys(rows.last(), false)?,
+            false => None,
+        };
+
+        let mut items = Vec::with_capacity(rows.len());
+        for row in &rows {
+            items.push(T::from_row(row)?);
+        }
+        Ok(CursorPage {
+            items,
+            next_cursor,
+            prev_cursor,
+        })
+    }
+
+    /// Executes SELECT queries walking the rows in chunks of `size`, ordered by `column`.
+    ///
+    /// Each chunk is selected with `WHERE column>? ORDER BY column LIMIT size`, binding the value
+    /// of `column` in the last row of the previous chunk. Unlike [`Query::offset`], rows are
+    /// neither skipped nor visited twice while the table is being modified. The column must be
+    /// selected, unique and not NULL, the ORDER BY, LIMIT and OFFSET clauses of the query are
+    /// ignored. As with [`Query::cursor_paginate`], the column must be an integer, float, boolean,
+    /// text or binary column.
+    ///
+    /// `f` is called with each chunk and returns `Ok(false)` to stop the iteration early.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sqlx::FromRow;
+    /// # use sorm::query::Query;
+    /// # use sorm::{Database, Result};
+    /// #[derive(FromRow)]
+    /// struct User {
+    ///     id: i64,
+    ///     name: String,
+    /// }
+    ///
+    /// async fn export_user(db: impl sqlx::Acquire<'_, Database = Database>) -> Result<()> {
+    ///     Query::table("users")
+    ///         .r#where("name<>''")
+    ///         .chunk_by(db, "id", 1000, |users: Vec<User>| async move {
+    ///             for user in users {
+    ///                 println!("{} {}", user.id, user.name);
+    ///             }
+    ///             Ok(true)
+    ///         })
+    ///         .await
+    ///     // SELECT * FROM `users` WHERE name<>'' ORDER BY `id` LIMIT 1000
+    ///     // SELECT * FROM `users` WHERE (name<>'') AND (`id`>?) ORDER BY `id` LIMIT 1000
+    /// }
+    /// ```
+    pub async fn chunk_by<'c, T, F, Fut>(
+        &self,
+        executor: impl Acquire<'c, Database = Database>,
+        column: &str,
+        size: usize,
+        mut f: F,
+    ) -> crate::Result<()>
+    where
+        T: for<'r> FromRow<'r, crate::Row> + Send + Unpin,
+        F: FnMut(Vec<T>) -> Fut,
+        Fut: Future<Output = crate::Result<bool>>,
+    {
+        let size = size.max(1);
+        let name = column.rsplit('.').next().unwrap_or(column);
+        let mut conn = executor.acquire().await?;
+        let mut last = None;
+        loop {
+            let values = last.as_ref().map(std::slice::from_ref);
+            let (sql, params, at, values) =
+                self.build_keyset(&[(column, false)], values, false, size);
+            let rows = sqlx::query_with(&sql, to_args_with(params, at, &values)?)
+                .fetch_all(&mut *conn)
+                .await?;
+            let Some(row) = rows.last() else {
+                return Ok(());
+            };
+            last = Some(Value::read(row, name)?);
+
+            let mut items = Vec::with_capacity(rows.len());
+            for row in &rows {
+                items.push(T::from_row(row)?);
+            }
+            if !f(items).await? || rows.len() < size {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Builds a SELECT query ordered by `columns`, selecting the rows after (or before) the key
+    /// `values` if any.
+    ///
+    /// Returns the index in the parameters where the key values belong, see [`to_args_with`].
+    #[allow(clippy::type_complexity)]
+    fn build_keyset<'v>(
+        &self,
+        columns: &[(&str, bool)],
+        values: Option<&'v [Value]>,
+        before: bool,
+        limit: usize,
+    ) -> (
+        String,
+        Vec<&'q (dyn Param<'q> + Sync)>,
+        usize,
+        Vec<&'v Value>,
+    ) {
+        let (expr_len, param_count) = self.criteria_size();
+        let mut sql = String::with_capacity(128 + expr_len);
+        let mut params = Vec::with_capacity(param_count);
+        self.write_with(&mut sql, &mut params);
+        self.write_columns(&mut sql, &mut params);
+        let (at, values) = match values {
+            Some(values) => {
+                let (expr, values) = keyset_condition(columns, values, before);
+                (self.write_from(&mut sql, &mut params, Some(&expr)), values)
+            }
+            None => (self.write_from(&mut sql, &mut params, None), Vec::new()),
+        };
+        sql.push_str(" ORDER BY ");
+        for (column, desc) in columns {
+            concat_ident(&mut sql, column);
+            if desc != &before {
+                sql.push_str(" DESC");
+            }
+            sql.push(',');
         }
+        sql.pop();
+        sql.push_str(&format!(" LIMIT {}", limit));
+        self.write_lock(&mut sql);
 
         #[cfg(feature = "postgres")]
         let sql = pg_replace_placeholder(&sql);
         debug!(target: "sorm", "{}", sql);
         #[cfg(feature = "test")]
+        {
+            let mut list: Vec<_> = params.iter().map(|v| v.to_string()).collect();
+            list.splice(at..at, values.iter().map(|v| v.debug()));
+            test::QUERY.push(sql.clone(), list);
+        }
+        (sql, params, at, values)
+    }
+
+    #[inline]
+    fn build_select(&self, limit: Option<usize>) -> (String, Vec<&'q (dyn Param<'q> + Sync)>) {
+        self.build(|sql, params| self.write_statement(sql, params, limit))
+    }
+
+    fn build(
+        &self,
+        write: impl FnOnce(&mut String, &mut Vec<&'q (dyn Param<'q> + Sync)>),
+    ) -> (String, Vec<&'q (dyn Param<'q> + Sync)>) {
+        let (sql, params) = self.render(write);
+        debug!(target: "sorm", "{}", sql);
+        #[cfg(feature = "test")]
         test::QUERY.add(&sql, &params);
         (sql, params)
     }
 
+    /// Writes the SQL without logging it, replacing the placeholders for PostgreSQL.
+    fn render(
+        &self,
+        write: impl FnOnce(&mut String, &mut Vec<&'q (dyn Param<'q> + Sync)>),
+    ) -> (String, Vec<&'q (dyn Param<'q> + Sync)>) {
+        let (expr_len, param_count) = self.criteria_size();
+        let mut sql = String::with_capacity(64 + expr_len);
+        let mut params = Vec::with_capacity(param_count);
+        write(&mut sql, &mut params);
+
+        #[cfg(feature = "postgres")]
+        let sql = pg_replace_placeholder(&sql);
+        (sql, params)
+    }
+
+    fn write_select(
+        &self,
+        sql: &mut String,
+        params: &mut Vec<&'q (dyn Param<'q> + Sync)>,
+        limit: Option<usize>,
+    ) {
+        self.write_with(sql, params);
+        self.write_select_body(sql, params);
+        self.write_order_limit(sql, params, limit, self.offset);
+    }
+
+    /// Writes the query as a top-level SELECT statement, the only place where the row lock is
+    /// valid: it is not written in compound members, CTE bodies and subqueries.
+    fn write_statement(
+        &self,
+        sql: &mut String,
+        params: &mut Vec<&'q (dyn Param<'q> + Sync)>,
+        limit: Option<usize>,
+    ) {
+        self.write_select(sql, params, limit);
+        self.write_lock(sql);
+    }
+
+    /// Writes a query selecting the aggregate `expr` over the rows of the query, ignoring its
+    /// ORDER BY, LIMIT and OFFSET clauses.
+    fn write_aggregate(
+        &self,
+        sql: &mut String,
+        params: &mut Vec<&'q (dyn Param<'q> + Sync)>,
+        expr: &str,
+    ) {
+        self.write_with(sql, params);
+        sql.push_str("SELECT ");
+        sql.push_str(expr);
+        if !self.is_grouped() {
+            self.write_from(sql, params, None);
+            return;
+        }
+
+        sql.push_str(" FROM (");
+        self.write_select_body(sql, params);
+        sql.push_str(") AS ");
+        concat_ident(sql, "t");
+    }
+
+    /// Returns whether the query has DISTINCT, GROUP BY or HAVING clauses or set operations,
+    /// whose rows must be aggregated through a subquery.
+    #[inline]
+    fn is_grouped(&self) -> bool {
+        !matches!(self.group_by, Select::None)
+            || self.distinct.is_some()
+            || !self.having.is_empty()
+            || !self.compounds.is_empty()
+    }
+
+    fn write_with(&self, sql: &mut String, params: &mut Vec<&'q (dyn Param<'q> + Sync)>) {
+        if self.ctes.is_empty() {
+            return;
+        }
+
+        sql.push_str("WITH ");
+        if self.ctes.iter().any(|v| v.recursive.is_some()) {
+            sql.push_str("RECURSIVE ");
+        }
+        for v in &self.ctes {
+            concat_ident(sql, v.name);
+            sql.push_str(" AS (");
+            v.query.write_select(sql, params, None);
+            if let Some(ref recursive) = v.recursive {
+                sql.push_str(" UNION ALL ");
+                recursive.write_select(sql, params, None);
+            }
+            sql.push_str("),");
+        }
+        sql.pop();
+        sql.push(' ');
+    }
+
+    /// Writes the SELECT statement without the ORDER BY, LIMIT and OFFSET clauses.
+    fn write_select_body(&self, sql: &mut String, params: &mut Vec<&'q (dyn Param<'q> + Sync)>) {
+        self.write_columns(sql, params);
+        self.write_from(sql, params, None);
+        for (op, query) in &self.compounds {
+            sql.push_str(op);
+            query.write_compound(sql, params);
+        }
+    }
+
+    fn write_columns(&self, sql: &mut String, params: &mut Vec<&'q (dyn Param<'q> + Sync)>) {
+        sql.push_str("SELECT ");
+        match self.distinct {
+            Some(ref columns) if columns.is_empty() => sql.push_str("DISTINCT "),
+            Some(ref columns) => {
+                sql.push_str("DISTINCT ON (");
+                concat_idents(sql, columns);
+                sql.push_str(") ");
+            }
+            None => (),
+        }
+        match self.select {
+            Select::Columns(ref fields) => concat_idents(sql, fields),
+            Select::Raw(expr, ref values) => {
+                sql.push_str(expr);
+                params.extend_from_slice(values);
+            }
+            Select::Omitted(ref omit) => {
+                for v in self.columns.unwrap() {
+                    if !omit.contains(v) {
+                        concat_ident(sql, v);
+                    }
+                }
+            }
+            Select::None => match self.columns {
+                Some(columns) => concat_idents(sql, columns),
+                None => sql.push('*'),
+            },
+        }
+    }
+
+    /// Writes the clauses from FROM to HAVING, adding the `extra` condition to the WHERE clause.
+    ///
+    /// Returns the index in `params` where the parameters of `extra` belong.
+    fn write_from(
+        &self,
+        sql: &mut String,
+        params: &mut Vec<&'q (dyn Param<'q> + Sync)>,
+        extra: Option<&str>,
+    ) -> usize {
+        sql.push_str(" FROM ");
+        self.write_source(sql, params);
+
+        for v in &self.joins {
+            sql.push(' ');
+            sql.push_str(v.kind);
+            sql.push(' ');
+            concat_table(sql, v.table, v.alias);
+            if let Some((expr, ref values)) = v.on {
+                sql.push_str(" ON ");
+                sql.push_str(expr);
+                params.extend_from_slice(values);
+            }
+        }
+
+        match extra {
+            Some(extra) => {
+                sql.push_str(" WHERE ");
+                if self.criteria.is_empty() {
+                    sql.push_str(extra);
+                } else {
+                    sql.push('(');
+                    write_conditions(sql, params, &self.criteria);
+                    sql.push_str(") AND (");
+                    sql.push_str(extra);
+                    sql.push(')');
+                }
+            }
+            None if !self.criteria.is_empty() => {
+                sql.push_str(" WHERE ");
+                write_conditions(sql, params, &self.criteria);
+            }
+            None => (),
+        }
+        let at = params.len();
+
+        match self.group_by {
+            Select::Columns(ref fields) => {
+                sql.push_str(" GROUP BY ");
+                concat_idents(sql, fields);
+            }
+            Select::Raw(expr, ref values) => {
+                sql.push_str(" GROUP BY ");
+                sql.push_str(expr);
+                params.extend_from_slice(values);
+            }
+            Select::Omitted(_) => unreachable!(),
+            Select::None => (),
+        }
+
+        if !self.having.is_empty() {
+            sql.push_str(" HAVING ");
+            write_conditions(sql, params, &self.having);
+        }
+        at
+    }
+
+    fn write_order_limit(
+        &self,
+        sql: &mut String,
+        params: &mut Vec<&'q (dyn Param<'q> + Sync)>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) {
+        if !self.order_by.is_empty() {
+            sql.push_str(" ORDER BY ");
+            for v in &self.order_by {
+                match v {
+                    OrderBy::Asc(v) => concat_ident(sql, v),
+                    OrderBy::Desc(v) => {
+                        concat_ident(sql, v);
+                        sql.push_str(" DESC");
+                    }
+                    OrderBy::Nulls(v, desc, nulls) => {
+                        if !Dialect::CURRENT.supports_nulls_order() {
+                            concat_ident(sql, v);
+                            sql.push_str(match nulls {
+                                Nulls::First => " IS NULL DESC,",
+                                Nulls::Last => " IS NULL,",
+                            });
+                        }
+                        concat_ident(sql, v);
+                        if *desc {
+                            sql.push_str(" DESC");
+                        }
+                        if Dialect::CURRENT.supports_nulls_order() {
+                            sql.push_str(match nulls {
+                                Nulls::First => " NULLS FIRST",
+                                Nulls::Last => " NULLS LAST",
+                            });
+                        }
+                    }
+                    OrderBy::Raw(expr, ref values) => {
+                        sql.push_str(expr);
+                        params.extend_from_slice(values);
+                    }
+                }
+                sql.push(',');
+            }
+            sql.pop();
+        }
+
+        if let Some(limit) = limit.or(self.limit) {
+            sql.push_str(&format!(" LIMIT {}", limit))
+        }
+        if let Some(offset) = offset {
+            sql.push_str(&format!(" OFFSET {}", offset))
+        }
+    }
+
+    fn write_lock(&self, sql: &mut String) {
+        if !Dialect::CURRENT.supports_row_lock() {
+            return;
+        }
+        if let Some(lock) = self.lock {
+            sql.push_str(lock);
+            if let Some(wait) = self.lock_wait {
+                sql.push_str(wait);
+            }
+        }
+    }
+
+    /// Writes a member of a compound select, wrapping it in a derived table
+    /// when it has clauses that would otherwise apply to the whole compound.
+    fn write_compound(&self, sql: &mut String, params: &mut Vec<&'q (dyn Param<'q> + Sync)>) {
+        if self.order_by.is_empty()
+            && self.limit.is_none()
+            && self.offset.is_none()
+            && self.compounds.is_empty()
+            && self.ctes.is_empty()
+        {
+            return self.write_select(sql, params, None);
+        }
+
+        sql.push_str("SELECT * FROM (");
+        self.write_select(sql, params, None);
+        sql.push_str(") AS ");
+        concat_ident(sql, "t");
+    }
+
+    fn write_source(&self, sql: &mut String, params: &mut Vec<&'q (dyn Param<'q> + Sync)>) {
+        match self.source {
+            Source::Table(table) => concat_table(sql, table, self.alias),
+            Source::Sub(ref query) => {
+                sql.push('(');
+                query.write_select(sql, params, None);
+                sql.push(')');
+                if let Some(alias) = self.alias {
+                    sql.push_str(" AS ");
+                    concat_ident(sql, alias);
+                }
+            }
+        }
+    }
+
+    /// Writes the table of an INSERT, UPDATE or DELETE query, without the alias which the
+    /// backends do not all accept there.
+    fn write_target(&self, sql: &mut String, params: &mut Vec<&'q (dyn Param<'q> + Sync)>) {
+        match self.source {
+            Source::Table(table) => concat_ident(sql, table),
+            Source::Sub(_) => self.write_source(sql, params),
+        }
+    }
+
     fn criteria_size(&self) -> (usize, usize) {
-        let mut s1 = 0;
-        let mut s2 = 0;
-        for v in &self.criteria {
-            s1 += v.expr().len();
-            s2 += v.params().len();
+        let (mut s1, mut s2) = conditions_size(&self.criteria);
+        let (having_len, having_count) = conditions_size(&self.having);
+        s1 += having_len;
+        s2 += having_count;
+
+        if let Source::Sub(ref query) = self.source {
+            let (sub_len, sub_count) = query.criteria_size();
+            s1 += 64 + sub_len;
+            s2 += sub_count;
+        }
+
+        for v in &self.ctes {
+            for query in std::iter::once(&v.query).chain(&v.recursive) {
+                let (len, count) = query.criteria_size();
+                s1 += 64 + len;
+                s2 += count;
+            }
         }
 
-        for v in &self.having {
-            s1 += v.expr().len();
-            s2 += v.params().len();
+        for (_, query) in &self.compounds {
+            let (len, count) = query.criteria_size();
+            s1 += 64 + len;
+            s2 += count;
+        }
+
+        for v in &self.joins {
+            s1 += v.table.len() + 16;
+            if let Some((expr, ref values)) = v.on {
+                s1 += expr.len();
+                s2 += values.len();
+            }
         }
 
         (s1, s2)
     }
 
+    /// Executes an INSERT query, returning the number of inserted rows.
+    ///
+    /// An empty `Insert` inserts a row of default values.
+    pub async fn insert(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+        insert: &Insert<'q>,
+    ) -> crate::Result<u64> {
+        insert.check()?;
+        let (sql, params) = self.build(|sql, params| self.write_insert(sql, params, insert, None));
+        let result = sqlx::query_with(&sql, to_args(params)?)
+            .execute(executor)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Executes an INSERT query, returning the id of the last inserted row.
+    ///
+    /// `column` is the auto incrementing primary key, it is only used for the `RETURNING`
+    /// clause of PostgreSQL. MySQL only reports the id of the first row of a multi-row insert,
+    /// the id of the last row is derived from it, assuming the ids of the rows are consecutive.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::query::{Insert, Query};
+    /// # use sorm::{Database, Result};
+    /// async fn add_user(
+    ///     db: impl sqlx::Executor<'_, Database = Database>,
+    ///     name: &str,
+    /// ) -> Result<u64> {
+    ///     Query::table("users")
+    ///         .insert_get_id(db, &Insert::new().set("name", &name), "id")
+    ///         .await
+    ///     // PostgreSQL: INSERT INTO "users" ("name") VALUES ($1) RETURNING CAST("id" AS BIGINT)
+    /// }
+    /// ```
+    #[cfg_attr(not(feature = "postgres"), allow(unused_variables))]
+    pub async fn insert_get_id(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+        insert: &Insert<'q>,
+        column: &str,
+    ) -> crate::Result<u64> {
+        insert.check()?;
+        #[cfg(feature = "postgres")]
+        {
+            let mut returning = String::with_capacity(column.len() + 18);
+            returning.push_str("CAST(");
+            concat_ident(&mut returning, column);
+            returning.push_str(" AS BIGINT)");
+            let (sql, params) =
+                self.build(|sql, params| self.write_insert(sql, params, insert, Some(&returning)));
+            let rows = sqlx::query_with(&sql, to_args(params)?)
+                .fetch_all(executor)
+                .await?;
+            let mut last = None;
+            for row in &rows {
+                let id: i64 = row.try_get(0)?;
+                last = last.max(Some(id));
+            }
+            Ok(last.ok_or(sqlx::Error::RowNotFound)? as u64)
+        }
+
+        #[cfg(not(feature = "postgres"))]
+        {
+            let (sql, params) =
+                self.build(|sql, params| self.write_insert(sql, params, insert, None));
+            let result = sqlx::query_with(&sql, to_args(params)?)
+                .execute(executor)
+                .await?;
+            #[cfg(feature = "sqlite")]
+            let id = result.last_insert_rowid() as u64;
+            #[cfg(feature = "mysql")]
+            let id = result.last_insert_id() + result.rows_affected().saturating_sub(1);
+            Ok(id)
+        }
+    }
+
+    /// Executes an INSERT query with a `RETURNING` clause, returning the given columns of the
+    /// inserted rows, or all the columns if `columns` is empty.
+    ///
+    /// It is not supported by MySQL.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sqlx::FromRow;
+    /// # use sorm::query::{Insert, Query};
+    /// # use sorm::{Database, Result};
+    /// #[derive(FromRow)]
+    /// struct User {
+    ///     id: i64,
+    ///     name: String,
+    /// }
+    ///
+    /// async fn archive_user(db: impl sqlx::Executor<'_, Database = Database>) -> Result<Vec<User>> {
+    ///     let mut deleted = Query::table("users");
+    ///     deleted.select(&["id", "name"]).r#where("deleted=1");
+    ///     Query::table("archived_users")
+    ///         .insert_returning(db, &Insert::select(&["id", "name"], &deleted), &[])
+    ///         .await
+    ///     // INSERT INTO `archived_users` (`id`,`name`) SELECT `id`,`name` FROM `users` WHERE deleted=1 RETURNING *
+    /// }
+    /// ```
+    #[cfg(any(feature = "sqlite", feature = "postgres"))]
+    pub async fn insert_returning<T>(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+        insert: &Insert<'q>,
+        columns: &[&str],
+    ) -> crate::Result<Vec<T>>
+    where
+        T: for<'r> FromRow<'r, crate::Row> + Send + Unpin,
+    {
+        insert.check()?;
+        let mut returning = String::with_capacity(columns.len() * 10 + 1);
+        match columns.is_empty() {
+            true => returning.push('*'),
+            false => concat_idents(&mut returning, columns),
+        }
+        let (sql, params) =
+            self.build(|sql, params| self.write_insert(sql, params, insert, Some(&returning)));
+        Ok(sqlx::query_as_with(&sql, to_args(params)?)
+            .fetch_all(executor)
+            .await?)
+    }
+
+    fn write_insert(
+        &self,
+        sql: &mut String,
+        params: &mut Vec<&'q (dyn Param<'q> + Sync)>,
+        insert: &Insert<'q>,
+        returning: Option<&str>,
+    ) {
+        self.write_with(sql, params);
+        sql.push_str("INSERT INTO ");
+        self.write_target(sql, params);
+        if insert.columns.is_empty() {
+            #[cfg(feature = "mysql")]
+            sql.push_str(" () VALUES ()");
+            #[cfg(not(feature = "mysql"))]
+            sql.push_str(" DEFAULT VALUES");
+        } else {
+            sql.push_str(" (");
+            concat_idents(sql, &insert.columns);
+            sql.push(')');
+            match insert.select {
+                Some(query) => {
+                    sql.push(' ');
+                    query.write_select(sql, params, None);
+                }
+                None => {
+                    sql.push_str(" VALUES (");
+                    sql.push_str(&insert.values);
+                    sql.push(')');
+                    params.extend_from_slice(&insert.params);
+                }
+            }
+        }
+
+        if let Some(returning) = returning {
+            sql.push_str(" RETURNING ");
+            sql.push_str(returning);
+        }
+    }
+
     /// Executes a UPDATE query.
+    ///
+    /// The alias of the table is left out, so the conditions must refer to the table by its
+    /// name. Returns [`Error::UnsupportedJoin`] if the query has JOIN clauses.
     pub async fn update(
         &self,
         executor: impl Executor<'q, Database = Database>,
         update: impl Clause<'q>,
     ) -> crate::Result<u64> {
-        if self.criteria.is_empty() {
-            return Err(Error::NoWhereClause);
-        }
-
-        let (expr_len, param_count) = self.criteria_size();
-        let mut sql = String::with_capacity(32 + expr_len);
-        let mut params = Vec::with_capacity(param_count);
-        params.extend_from_slice(update.params());
+        self.check_mutation()?;
 
-        sql.push_str("UPDATE ");
-        concat_ident(&mut sql, self.table);
-        sql.push_str(" SET ");
-        sql.push_str(update.expr());
-        sql.push_str(" WHERE ");
-        for v in &self.criteria {
-            sql.push_str(v.expr());
-            params.extend_from_slice(v.params());
-        }
-        #[cfg(feature = "postgres")]
-        let sql = pg_replace_placeholder(&sql);
-        debug!(target: "sorm", "{}", sql);
-        #[cfg(feature = "test")]
-        test::QUERY.add(&sql, &params);
+        let (sql, params) = self.build(|sql, params| self.write_update(sql, params, &update));
         let result = sqlx::query_with(&sql, to_args(params)?)
             .execute(executor)
             .await?;
@@ -664,53 +2534,401 @@ impl<'q> Query<'q> {
     }
 
     /// Executes a DELETE query.
+    ///
+    /// As with [`Query::update`], the alias of the table is left out and JOIN clauses are
+    /// rejected.
     pub async fn delete(
         &self,
         executor: impl Executor<'q, Database = Database>,
     ) -> crate::Result<u64> {
+        self.check_mutation()?;
+
+        let (sql, params) = self.build(|sql, params| self.write_delete(sql, params));
+        let result = sqlx::query_with(&sql, to_args(params)?)
+            .execute(executor)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Checks that the query can be executed as an UPDATE or DELETE query.
+    fn check_mutation(&self) -> crate::Result<()> {
         if self.criteria.is_empty() {
             return Err(Error::NoWhereClause);
         }
+        if !self.joins.is_empty() {
+            return Err(Error::UnsupportedJoin);
+        }
+        Ok(())
+    }
 
-        let (expr_len, param_count) = self.criteria_size();
-        let mut sql = String::with_capacity(32 + expr_len);
-        let mut params = Vec::with_capacity(param_count);
+    fn write_update(
+        &self,
+        sql: &mut String,
+        params: &mut Vec<&'q (dyn Param<'q> + Sync)>,
+        update: &impl Clause<'q>,
+    ) {
+        self.write_with(sql, params);
+        sql.push_str("UPDATE ");
+        self.write_target(sql, params);
+        sql.push_str(" SET ");
+        sql.push_str(update.expr());
+        params.extend_from_slice(update.params());
+        sql.push_str(" WHERE ");
+        write_conditions(sql, params, &self.criteria);
+    }
 
+    fn write_delete(&self, sql: &mut String, params: &mut Vec<&'q (dyn Param<'q> + Sync)>) {
+        self.write_with(sql, params);
         sql.push_str("DELETE FROM ");
-        concat_ident(&mut sql, self.table);
+        self.write_target(sql, params);
         sql.push_str(" WHERE ");
-        for v in &self.criteria {
-            sql.push_str(v.expr());
-            params.extend_from_slice(v.params());
+        write_conditions(sql, params, &self.criteria);
+    }
+
+    /// Returns the SELECT statement of the query, without executing it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use sorm::clause;
+    /// # use sorm::query::Query;
+    /// let status = 1;
+    /// let params;
+    /// let mut query = Query::table("users");
+    /// query.select(&["id"]).r#where(clause!("status={status}", params));
+    /// let sql = query.to_sql();
+    /// // sql.sql => SELECT `id` FROM `users` WHERE status=?
+    /// // sql.params => ["1"] with the `test` feature, ["i32"] otherwise
+    /// assert_eq!(sql.params.len(), 1);
+    /// ```
+    pub fn to_sql(&self) -> Sql {
+        let (sql, params) = self.render(|sql, params| self.write_statement(sql, params, None));
+        Sql::new(sql, &params)
+    }
+
+    /// Returns the UPDATE statement of [`Query::update`], without executing it.
+    ///
+    /// Returns the same errors as [`Query::update`] for a query without WHERE clause or with
+    /// JOIN clauses.
+    pub fn to_update_sql(&self, update: impl Clause<'q>) -> crate::Result<Sql> {
+        self.check_mutation()?;
+        let (sql, params) = self.render(|sql, params| self.write_update(sql, params, &update));
+        Ok(Sql::new(sql, &params))
+    }
+
+    /// Returns the DELETE statement of [`Query::delete`], without executing it.
+    ///
+    /// Returns the same errors as [`Query::delete`].
+    pub fn to_delete_sql(&self) -> crate::Result<Sql> {
+        self.check_mutation()?;
+        let (sql, params) = self.render(|sql, params| self.write_delete(sql, params));
+        Ok(Sql::new(sql, &params))
+    }
+
+    /// Returns the plan of the SELECT statement of the query.
+    ///
+    /// The statement is prefixed with `EXPLAIN QUERY PLAN` on SQLite and `EXPLAIN` on MySQL
+    /// and PostgreSQL, and the rows are returned as is, since their columns differ per backend:
+    /// the plan is in the `detail` column on SQLite and in the `QUERY PLAN` column, one row per
+    /// line, on PostgreSQL.
+    pub async fn explain(
+        &self,
+        executor: impl Executor<'_, Database = Database>,
+    ) -> crate::Result<Vec<crate::Row>> {
+        let (sql, params) = self.build(|sql, params| {
+            #[cfg(feature = "sqlite")]
+            sql.push_str("EXPLAIN QUERY PLAN ");
+            #[cfg(not(feature = "sqlite"))]
+            sql.push_str("EXPLAIN ");
+            self.write_statement(sql, params, None)
+        });
+        Ok(sqlx::query_with(&sql, to_args(params)?)
+            .fetch_all(executor)
+            .await?)
+    }
+}
+
+impl<'q> AsRef<Query<'q>> for Query<'q> {
+    #[inline]
+    fn as_ref(&self) -> &Query<'q> {
+        self
+    }
+}
+
+impl<'q> AsMut<Query<'q>> for Query<'q> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut Query<'q> {
+        self
+    }
+}
+
+fn add_condition<'q>(
+    conditions: &mut Vec<Condition<'q>>,
+    connector: Condition<'q>,
+    condition: Condition<'q>,
+) {
+    if !conditions.is_empty() {
+        conditions.push(connector);
+    }
+    conditions.push(condition);
+}
+
+fn add_group<'q>(
+    conditions: &mut Vec<Condition<'q>>,
+    connector: Condition<'q>,
+    group: Vec<Condition<'q>>,
+) {
+    if !group.is_empty() {
+        add_condition(conditions, connector, Condition::Group(group));
+    }
+}
+
+/// Writes the conditions, wrapping each clause in parentheses when there is more than one
+/// so that the operators inside a clause do not leak into the others.
+fn write_conditions<'q>(
+    sql: &mut String,
+    params: &mut Vec<&'q (dyn Param<'q> + Sync)>,
+    conditions: &[Condition<'q>],
+) {
+    let wrap = conditions.len() > 1;
+    for v in conditions {
+        match *v {
+            Condition::And => sql.push_str(" AND "),
+            Condition::Or => sql.push_str(" OR "),
+            Condition::Clause(expr, ref p) if wrap => {
+                sql.push('(');
+                sql.push_str(expr);
+                sql.push(')');
+                params.extend_from_slice(p);
+            }
+            Condition::Clause(expr, ref p) => {
+                sql.push_str(expr);
+                params.extend_from_slice(p);
+            }
+            Condition::Group(ref group) => {
+                sql.push('(');
+                write_conditions(sql, params, group);
+                sql.push(')');
+            }
+            Condition::In(column, ref query) => {
+                concat_ident(sql, column);
+                sql.push_str(" IN (");
+                query.write_select(sql, params, None);
+                sql.push(')');
+            }
+            Condition::Exists(ref query) => {
+                sql.push_str("EXISTS (");
+                query.write_select(sql, params, None);
+                sql.push(')');
+            }
+        }
+    }
+}
+
+fn conditions_size(conditions: &[Condition]) -> (usize, usize) {
+    let mut s1 = 0;
+    let mut s2 = 0;
+    for v in conditions {
+        match *v {
+            Condition::And | Condition::Or => s1 += 5,
+            Condition::Clause(expr, ref params) => {
+                s1 += expr.len() + 2;
+                s2 += params.len();
+            }
+            Condition::Group(ref group) => {
+                let (len, count) = conditions_size(group);
+                s1 += len + 2;
+                s2 += count;
+            }
+            Condition::In(_, ref query) | Condition::Exists(ref query) => {
+                let (len, count) = query.criteria_size();
+                s1 += 64 + len;
+                s2 += count;
+            }
+        }
+    }
+    (s1, s2)
+}
+
+/// Builds the condition selecting the rows after (or before) the key `values` in the order of
+/// `columns`, whose second element indicates a descending order.
+fn keyset_condition<'v>(
+    columns: &[(&str, bool)],
+    values: &'v [Value],
+    before: bool,
+) -> (String, Vec<&'v Value>) {
+    let op = |desc: bool| if desc == before { ">" } else { "<" };
+    let mut expr = String::with_capacity(32 * columns.len());
+    if columns.len() == 1 || columns.iter().all(|v| v.1 == columns[0].1) {
+        if columns.len() > 1 {
+            expr.push('(');
+        }
+        for (column, _) in columns {
+            concat_ident(&mut expr, column);
+            expr.push(',');
+        }
+        expr.pop();
+        if columns.len() > 1 {
+            expr.push(')');
+        }
+        expr.push_str(op(columns[0].1));
+        if columns.len() > 1 {
+            expr.push('(');
+        }
+        for _ in columns {
+            expr.push_str("?,");
+        }
+        expr.pop();
+        if columns.len() > 1 {
+            expr.push(')');
+        }
+        return (expr, values.iter().collect());
+    }
+
+    let mut params = Vec::with_capacity(columns.len() * (columns.len() + 1) / 2);
+    for (i, (column, desc)) in columns.iter().enumerate() {
+        if i > 0 {
+            expr.push_str(" OR (");
+        }
+        for (j, (column, _)) in columns[..i].iter().enumerate() {
+            concat_ident(&mut expr, column);
+            expr.push_str("=? AND ");
+            params.push(&values[j]);
+        }
+        concat_ident(&mut expr, column);
+        expr.push_str(op(*desc));
+        expr.push('?');
+        params.push(&values[i]);
+        if i > 0 {
+            expr.push(')');
         }
-        #[cfg(feature = "postgres")]
-        let sql = pg_replace_placeholder(&sql);
-        debug!(target: "sorm", "{}", sql);
-        #[cfg(feature = "test")]
-        test::QUERY.add(&sql, &params);
-        let result = sqlx::query_with(&sql, to_args(params)?)
-            .execute(executor)
-            .await?;
-        Ok(result.rows_affected())
     }
+    (expr, params)
 }
 
+fn concat_table(s: &mut String, table: &str, alias: Option<&str>) {
+    concat_ident(s, table);
+    if let Some(alias) = alias {
+        s.push_str(" AS ");
+        concat_ident(s, alias);
+    }
+}
+
+/// Replaces the `?` placeholders with `$1`, `$2`, ..., skipping string literals, quoted
+/// identifiers, dollar-quoted strings and comments.
+///
+/// `??` is written as a literal `?`, e.g. for the JSONB operators `?`, `?|` and `?&`.
 #[cfg(feature = "postgres")]
-fn pg_replace_placeholder(sql: &str) -> String {
-    let mut s = String::with_capacity(sql.len());
+pub(crate) fn pg_replace_placeholder(sql: &str) -> String {
+    let bytes = sql.as_bytes();
+    let mut s = String::with_capacity(sql.len() + 8);
     let mut num = 1;
-    for c in sql.chars() {
-        match c {
-            '?' => {
-                s.push_str(&format!("${}", num));
-                num += 1;
+    // The positions are at ASCII characters, which are never part of a multibyte character.
+    let (mut written, mut pos) = (0, 0);
+    while pos < bytes.len() {
+        pos = match bytes[pos] {
+            b'?' => {
+                s.push_str(&sql[written..pos]);
+                if bytes.get(pos + 1) == Some(&b'?') {
+                    s.push('?');
+                    written = pos + 2;
+                } else {
+                    s.push_str(&format!("${}", num));
+                    num += 1;
+                    written = pos + 1;
+                }
+                written
             }
-            _ => s.push(c),
-        }
+            b'\'' => {
+                let escape = pos > 0
+                    && matches!(bytes[pos - 1], b'E' | b'e')
+                    && (pos < 2 || !is_ident_byte(bytes[pos - 2]));
+                skip_quoted(bytes, pos, b'\'', escape)
+            }
+            b'"' => skip_quoted(bytes, pos, b'"', false),
+            b'-' if bytes.get(pos + 1) == Some(&b'-') => bytes[pos..]
+                .iter()
+                .position(|v| *v == b'\n')
+                .map_or(bytes.len(), |v| pos + v + 1),
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => skip_block_comment(bytes, pos),
+            b'$' if pos == 0 || !is_ident_byte(bytes[pos - 1]) => skip_dollar_quoted(bytes, pos),
+            _ => pos + 1,
+        };
     }
+    s.push_str(&sql[written..]);
     s
 }
 
+#[cfg(feature = "postgres")]
+#[inline]
+fn is_ident_byte(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c == b'$' || c >= 0x80
+}
+
+/// Returns the position after the literal or quoted identifier starting at `start`, where a
+/// doubled quote is an escaped quote, as is a quote after a backslash in `E'...'` strings.
+#[cfg(feature = "postgres")]
+fn skip_quoted(bytes: &[u8], start: usize, quote: u8, backslash: bool) -> usize {
+    let mut pos = start + 1;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' if backslash => pos += 2,
+            c if c == quote => {
+                if bytes.get(pos + 1) != Some(&quote) {
+                    return pos + 1;
+                }
+                pos += 2;
+            }
+            _ => pos += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Returns the position after the block comment starting at `start`, which may be nested.
+#[cfg(feature = "postgres")]
+fn skip_block_comment(bytes: &[u8], start: usize) -> usize {
+    let (mut pos, mut depth) = (start + 2, 1);
+    while pos < bytes.len() {
+        match (bytes[pos], bytes.get(pos + 1)) {
+            (b'/', Some(b'*')) => {
+                depth += 1;
+                pos += 2;
+            }
+            (b'*', Some(b'/')) => {
+                depth -= 1;
+                pos += 2;
+                if depth == 0 {
+                    return pos;
+                }
+            }
+            _ => pos += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Returns the position after the `$tag$...$tag$` string starting at `start`, or the next
+/// position if it doesn't start a dollar-quoted string, like the `$1` parameters.
+#[cfg(feature = "postgres")]
+fn skip_dollar_quoted(bytes: &[u8], start: usize) -> usize {
+    let mut end = start + 1;
+    if bytes.get(end).is_some_and(u8::is_ascii_digit) {
+        return end;
+    }
+    while end < bytes.len() && bytes[end] != b'$' && is_ident_byte(bytes[end]) {
+        end += 1;
+    }
+    if bytes.get(end) != Some(&b'$') {
+        return start + 1;
+    }
+    let tag = &bytes[start..=end];
+    bytes[end + 1..]
+        .windows(tag.len())
+        .position(|v| v == tag)
+        .map_or(bytes.len(), |v| end + 1 + v + tag.len())
+}
+
 #[cfg(feature = "sqlite")]
 fn to_args<'q>(
     params: Vec<&'q (dyn Param<'q> + Sync)>,
@@ -723,6 +2941,46 @@ fn to_args<'q>(
     Ok(args)
 }
 
+#[cfg(feature = "sqlite")]
+fn to_args_with<'q>(
+    params: Vec<&'q (dyn Param<'q> + Sync)>,
+    at: usize,
+    values: &[&Value],
+) -> Result<crate::Arguments<'q>, BoxDynError> {
+    let mut args = crate::Arguments::default();
+    args.reserve(params.len() + values.len(), params.len() + values.len());
+    for v in &params[..at] {
+        v.add(&mut args)?;
+    }
+    for v in values {
+        v.add(&mut args)?;
+    }
+    for v in &params[at..] {
+        v.add(&mut args)?;
+    }
+    Ok(args)
+}
+
+#[cfg(any(feature = "mysql", feature = "postgres"))]
+fn to_args_with<'q>(
+    params: Vec<&'q (dyn Param<'q> + Sync)>,
+    at: usize,
+    values: &[&Value],
+) -> Result<crate::Arguments, BoxDynError> {
+    let mut args = crate::Arguments::default();
+    args.reserve(params.len() + values.len(), params.len() + values.len());
+    for v in &params[..at] {
+        v.add(&mut args)?;
+    }
+    for v in values {
+        v.add(&mut args)?;
+    }
+    for v in &params[at..] {
+        v.add(&mut args)?;
+    }
+    Ok(args)
+}
+
 #[cfg(any(feature = "mysql", feature = "postgres"))]
 fn to_args<'q>(params: Vec<&'q (dyn Param<'q> + Sync)>) -> Result<crate::Arguments, BoxDynError> {
     let mut args = crate::Arguments::default();
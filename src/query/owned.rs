@@ -0,0 +1,643 @@
+//! Owned query builders, which can be stored, cloned and sent to other tasks.
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use super::{
+    parse_sort_checked, Condition, Cte, Join, Nulls, OrderBy, Params, Query, Select, Source,
+    SubQuery, Table,
+};
+use crate::Param;
+
+/// An owned parameter of a [`ClauseOwned`].
+pub type ParamOwned = Arc<dyn for<'q> Param<'q> + Send + Sync>;
+
+/// An owned SQL clause with its parameters, the owned counterpart of [`crate::clause!`].
+///
+/// The placeholders of the parameters are written as `?`, for every backend.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorm::query::ClauseOwned;
+/// let clause = ClauseOwned::new("name=? AND age>?")
+///     .bind("foo".to_string())
+///     .bind(18);
+/// ```
+#[derive(Clone, Default)]
+pub struct ClauseOwned {
+    expr: String,
+    params: Vec<ParamOwned>,
+}
+
+impl ClauseOwned {
+    /// Constructs a new clause from a SQL expression.
+    #[inline]
+    pub fn new(expr: impl Into<String>) -> Self {
+        Self {
+            expr: expr.into(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Binds the value of the next placeholder.
+    pub fn bind<T>(mut self, value: T) -> Self
+    where
+        T: for<'q> Param<'q> + Send + Sync + 'static,
+    {
+        self.params.push(Arc::new(value));
+        self
+    }
+
+    fn params(&self) -> Params<'_> {
+        self.params
+            .iter()
+            .map(|v| &**v as &(dyn Param + Sync))
+            .collect()
+    }
+}
+
+impl From<&str> for ClauseOwned {
+    #[inline]
+    fn from(expr: &str) -> Self {
+        Self::new(expr)
+    }
+}
+
+impl From<String> for ClauseOwned {
+    #[inline]
+    fn from(expr: String) -> Self {
+        Self::new(expr)
+    }
+}
+
+#[derive(Clone)]
+enum SelectOwned {
+    Columns(Vec<String>),
+    Raw(ClauseOwned),
+    None,
+}
+
+#[derive(Clone)]
+enum SourceOwned {
+    Table(String),
+    Sub(Box<QueryOwned>),
+}
+
+#[derive(Clone)]
+enum ConditionOwned {
+    And,
+    Or,
+    Clause(ClauseOwned),
+    In(String, QueryOwned),
+    Exists(QueryOwned),
+    Group(Vec<ConditionOwned>),
+}
+
+#[derive(Clone)]
+struct CteOwned {
+    name: String,
+    query: QueryOwned,
+    recursive: Option<QueryOwned>,
+}
+
+#[derive(Clone)]
+struct JoinOwned {
+    kind: &'static str,
+    table: String,
+    alias: Option<String>,
+    on: Option<ClauseOwned>,
+}
+
+#[derive(Clone)]
+enum OrderByOwned {
+    Asc(String),
+    Desc(String),
+    Nulls(String, bool, Nulls),
+    Raw(ClauseOwned),
+}
+
+/// An owned query builder, which unlike [`Query`] does not borrow its columns, clauses and
+/// parameters, so it can be built in a function and returned, cached or moved to another task.
+///
+/// It has the builder methods of [`Query`], taking owned subqueries and [`ClauseOwned`]
+/// clauses. Queries are executed through the [`Query`] borrowed from [`QueryOwned::as_query`].
+///
+/// There is no owned counterpart of the model query returned by
+/// [`crate::model::Model::query`]: build the query from the table and the columns of the model,
+/// and decode the rows into the model with the generic methods of [`Query`], like
+/// `query.as_query().get::<User>(pool)`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sqlx::FromRow;
+/// # use sorm::query::{ClauseOwned, QueryOwned};
+/// # use sorm::{Database, Result};
+/// #[derive(FromRow)]
+/// struct User {
+///     id: i64,
+///     name: String,
+/// }
+///
+/// fn search(name: Option<String>) -> QueryOwned {
+///     let mut query = QueryOwned::table("users");
+///     if let Some(name) = name {
+///         query.r#where(ClauseOwned::new("name=?").bind(name));
+///     }
+///     query.order_by_desc("id");
+///     query
+/// }
+///
+/// async fn list_user(pool: &sqlx::Pool<Database>) -> Result<Vec<User>> {
+///     let query = search(Some("foo".to_string()));
+///     query.as_query().get(pool).await
+/// }
+/// ```
+#[derive(Clone)]
+pub struct QueryOwned {
+    ctes: Vec<CteOwned>,
+    source: SourceOwned,
+    alias: Option<String>,
+    select: SelectOwned,
+    joins: Vec<JoinOwned>,
+    criteria: Vec<ConditionOwned>,
+    group_by: SelectOwned,
+    having: Vec<ConditionOwned>,
+    order_by: Vec<OrderByOwned>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    compounds: Vec<(&'static str, QueryOwned)>,
+    distinct: Option<Vec<String>>,
+    lock: Option<&'static str>,
+    lock_wait: Option<&'static str>,
+}
+
+impl QueryOwned {
+    fn new(source: SourceOwned, alias: Option<String>) -> Self {
+        Self {
+            ctes: Vec::new(),
+            source,
+            alias,
+            select: SelectOwned::None,
+            joins: Vec::new(),
+            criteria: Vec::new(),
+            group_by: SelectOwned::None,
+            having: Vec::new(),
+            order_by: Vec::new(),
+            offset: None,
+            limit: None,
+            compounds: Vec::new(),
+            distinct: None,
+            lock: None,
+            lock_wait: None,
+        }
+    }
+
+    /// See [`Query::table`].
+    #[inline]
+    pub fn table<'a>(table: impl Table<'a>) -> Self {
+        Self::new(
+            SourceOwned::Table(table.name().to_owned()),
+            table.alias().map(str::to_owned),
+        )
+    }
+
+    /// See [`Query::from_sub`].
+    #[inline]
+    pub fn from_sub(query: QueryOwned, alias: &str) -> Self {
+        Self::new(SourceOwned::Sub(Box::new(query)), Some(alias.to_owned()))
+    }
+
+    /// See [`Query::alias`].
+    #[inline]
+    pub fn alias(&mut self, alias: &str) -> &mut Self {
+        self.alias = Some(alias.to_owned());
+        self
+    }
+
+    /// See [`Query::join`].
+    #[inline]
+    pub fn join<'a>(&mut self, table: impl Table<'a>, on: impl Into<ClauseOwned>) -> &mut Self {
+        self.add_join("INNER JOIN", table, Some(on.into()))
+    }
+
+    /// See [`Query::left_join`].
+    #[inline]
+    pub fn left_join<'a>(
+        &mut self,
+        table: impl Table<'a>,
+        on: impl Into<ClauseOwned>,
+    ) -> &mut Self {
+        self.add_join("LEFT JOIN", table, Some(on.into()))
+    }
+
+    /// See [`Query::right_join`].
+    #[inline]
+    pub fn right_join<'a>(
+        &mut self,
+        table: impl Table<'a>,
+        on: impl Into<ClauseOwned>,
+    ) -> &mut Self {
+        self.add_join("RIGHT JOIN", table, Some(on.into()))
+    }
+
+    /// See [`Query::cross_join`].
+    #[inline]
+    pub fn cross_join<'a>(&mut self, table: impl Table<'a>) -> &mut Self {
+        self.add_join("CROSS JOIN", table, None)
+    }
+
+    fn add_join<'a>(
+        &mut self,
+        kind: &'static str,
+        table: impl Table<'a>,
+        on: Option<ClauseOwned>,
+    ) -> &mut Self {
+        self.joins.push(JoinOwned {
+            kind,
+            table: table.name().to_owned(),
+            alias: table.alias().map(str::to_owned),
+            on,
+        });
+        self
+    }
+
+    /// See [`Query::with`].
+    #[inline]
+    pub fn with(&mut self, name: &str, query: QueryOwned) -> &mut Self {
+        self.ctes.push(CteOwned {
+            name: name.to_owned(),
+            query,
+            recursive: None,
+        });
+        self
+    }
+
+    /// See [`Query::with_recursive`].
+    #[inline]
+    pub fn with_recursive(
+        &mut self,
+        name: &str,
+        anchor: QueryOwned,
+        recursive: QueryOwned,
+    ) -> &mut Self {
+        self.ctes.push(CteOwned {
+            name: name.to_owned(),
+            query: anchor,
+            recursive: Some(recursive),
+        });
+        self
+    }
+
+    /// See [`Query::distinct`].
+    #[inline]
+    pub fn distinct(&mut self) -> &mut Self {
+        self.distinct = Some(Vec::new());
+        self
+    }
+
+    /// See [`Query::distinct_on`].
+    #[cfg(feature = "postgres")]
+    #[inline]
+    pub fn distinct_on(&mut self, columns: &[&str]) -> &mut Self {
+        self.distinct = Some(to_strings(columns));
+        self
+    }
+
+    /// See [`Query::select`].
+    #[inline]
+    pub fn select(&mut self, columns: &[&str]) -> &mut Self {
+        self.select = SelectOwned::Columns(to_strings(columns));
+        self
+    }
+
+    /// See [`Query::select_raw`].
+    #[inline]
+    pub fn select_raw(&mut self, expr: impl Into<ClauseOwned>) -> &mut Self {
+        self.select = SelectOwned::Raw(expr.into());
+        self
+    }
+
+    /// See [`Query::r#where`].
+    pub fn r#where(&mut self, clause: impl Into<ClauseOwned>) -> &mut Self {
+        add_condition(
+            &mut self.criteria,
+            ConditionOwned::And,
+            ConditionOwned::Clause(clause.into()),
+        );
+        self
+    }
+
+    /// See [`Query::or_where`].
+    pub fn or_where(&mut self, clause: impl Into<ClauseOwned>) -> &mut Self {
+        add_condition(
+            &mut self.criteria,
+            ConditionOwned::Or,
+            ConditionOwned::Clause(clause.into()),
+        );
+        self
+    }
+
+    /// See [`Query::where_group`].
+    pub fn where_group(&mut self, f: impl FnOnce(&mut QueryOwned)) -> &mut Self {
+        let mut query = QueryOwned::table("");
+        f(&mut query);
+        add_group(&mut self.criteria, ConditionOwned::And, query.criteria);
+        self
+    }
+
+    /// See [`Query::or_where_group`].
+    pub fn or_where_group(&mut self, f: impl FnOnce(&mut QueryOwned)) -> &mut Self {
+        let mut query = QueryOwned::table("");
+        f(&mut query);
+        add_group(&mut self.criteria, ConditionOwned::Or, query.criteria);
+        self
+    }
+
+    /// See [`Query::where_in_sub`].
+    pub fn where_in_sub(&mut self, column: &str, query: QueryOwned) -> &mut Self {
+        add_condition(
+            &mut self.criteria,
+            ConditionOwned::And,
+            ConditionOwned::In(column.to_owned(), query),
+        );
+        self
+    }
+
+    /// See [`Query::where_exists`].
+    pub fn where_exists(&mut self, query: QueryOwned) -> &mut Self {
+        add_condition(
+            &mut self.criteria,
+            ConditionOwned::And,
+            ConditionOwned::Exists(query),
+        );
+        self
+    }
+
+    /// See [`Query::group_by`].
+    #[inline]
+    pub fn group_by(&mut self, fields: &[&str]) -> &mut Self {
+        self.group_by = SelectOwned::Columns(to_strings(fields));
+        self
+    }
+
+    /// See [`Query::group_by_raw`].
+    #[inline]
+    pub fn group_by_raw(&mut self, expr: impl Into<ClauseOwned>) -> &mut Self {
+        self.group_by = SelectOwned::Raw(expr.into());
+        self
+    }
+
+    /// See [`Query::having`].
+    pub fn having(&mut self, clause: impl Into<ClauseOwned>) -> &mut Self {
+        add_condition(
+            &mut self.having,
+            ConditionOwned::And,
+            ConditionOwned::Clause(clause.into()),
+        );
+        self
+    }
+
+    /// See [`Query::or_having`].
+    pub fn or_having(&mut self, clause: impl Into<ClauseOwned>) -> &mut Self {
+        add_condition(
+            &mut self.having,
+            ConditionOwned::Or,
+            ConditionOwned::Clause(clause.into()),
+        );
+        self
+    }
+
+    /// See [`Query::having_group`].
+    pub fn having_group(&mut self, f: impl FnOnce(&mut QueryOwned)) -> &mut Self {
+        let mut query = QueryOwned::table("");
+        f(&mut query);
+        add_group(&mut self.having, ConditionOwned::And, query.having);
+        self
+    }
+
+    /// See [`Query::or_having_group`].
+    pub fn or_having_group(&mut self, f: impl FnOnce(&mut QueryOwned)) -> &mut Self {
+        let mut query = QueryOwned::table("");
+        f(&mut query);
+        add_group(&mut self.having, ConditionOwned::Or, query.having);
+        self
+    }
+
+    /// See [`Query::order_by`].
+    #[inline]
+    pub fn order_by(&mut self, order_by: &str) -> &mut Self {
+        self.order_by.push(OrderByOwned::Asc(order_by.to_owned()));
+        self
+    }
+
+    /// See [`Query::order_by_desc`].
+    #[inline]
+    pub fn order_by_desc(&mut self, order_by: &str) -> &mut Self {
+        self.order_by.push(OrderByOwned::Desc(order_by.to_owned()));
+        self
+    }
+
+    /// See [`Query::order_by_raw`].
+    #[inline]
+    pub fn order_by_raw(&mut self, order_by: impl Into<ClauseOwned>) -> &mut Self {
+        self.order_by.push(OrderByOwned::Raw(order_by.into()));
+        self
+    }
+
+    /// See [`Query::order_by_checked`].
+    pub fn order_by_checked(&mut self, input: &str, allowed: &[&str]) -> crate::Result<&mut Self> {
+        let fields = parse_sort_checked(input, allowed)?;
+        self.order_by
+            .extend(fields.into_iter().map(|(column, desc, nulls)| match nulls {
+                Some(nulls) => OrderByOwned::Nulls(column.to_owned(), desc, nulls),
+                None if desc => OrderByOwned::Desc(column.to_owned()),
+                None => OrderByOwned::Asc(column.to_owned()),
+            }));
+        Ok(self)
+    }
+
+    /// See [`Query::offset`].
+    #[inline]
+    pub fn offset(&mut self, offset: usize) -> &mut Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// See [`Query::limit`].
+    #[inline]
+    pub fn limit(&mut self, limit: usize) -> &mut Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// See [`Query::lock_for_update`].
+    #[inline]
+    pub fn lock_for_update(&mut self) -> &mut Self {
+        self.lock = Some(" FOR UPDATE");
+        self
+    }
+
+    /// See [`Query::lock_for_share`].
+    #[inline]
+    pub fn lock_for_share(&mut self) -> &mut Self {
+        self.lock = Some(" FOR SHARE");
+        self
+    }
+
+    /// See [`Query::skip_locked`].
+    #[inline]
+    pub fn skip_locked(&mut self) -> &mut Self {
+        self.lock_wait = Some(" SKIP LOCKED");
+        self
+    }
+
+    /// See [`Query::nowait`].
+    #[inline]
+    pub fn nowait(&mut self) -> &mut Self {
+        self.lock_wait = Some(" NOWAIT");
+        self
+    }
+
+    /// See [`Query::union`].
+    #[inline]
+    pub fn union(&mut self, query: QueryOwned) -> &mut Self {
+        self.compounds.push((" UNION ", query));
+        self
+    }
+
+    /// See [`Query::union_all`].
+    #[inline]
+    pub fn union_all(&mut self, query: QueryOwned) -> &mut Self {
+        self.compounds.push((" UNION ALL ", query));
+        self
+    }
+
+    /// See [`Query::intersect`].
+    #[inline]
+    pub fn intersect(&mut self, query: QueryOwned) -> &mut Self {
+        self.compounds.push((" INTERSECT ", query));
+        self
+    }
+
+    /// See [`Query::except`].
+    #[inline]
+    pub fn except(&mut self, query: QueryOwned) -> &mut Self {
+        self.compounds.push((" EXCEPT ", query));
+        self
+    }
+
+    /// Returns a [`Query`] borrowing the columns, clauses and parameters of this query, to
+    /// execute it.
+    pub fn as_query(&self) -> Query<'_> {
+        Query {
+            ctes: self
+                .ctes
+                .iter()
+                .map(|v| Cte {
+                    name: &v.name,
+                    query: v.query.as_sub(),
+                    recursive: v.recursive.as_ref().map(QueryOwned::as_sub),
+                })
+                .collect(),
+            source: match self.source {
+                SourceOwned::Table(ref table) => Source::Table(table),
+                SourceOwned::Sub(ref query) => Source::Sub(query.as_sub()),
+            },
+            alias: self.alias.as_deref(),
+            columns: None,
+            select: as_select(&self.select),
+            joins: self
+                .joins
+                .iter()
+                .map(|v| Join {
+                    kind: v.kind,
+                    table: &v.table,
+                    alias: v.alias.as_deref(),
+                    on: v.on.as_ref().map(|on| (on.expr.as_str(), on.params())),
+                })
+                .collect(),
+            criteria: as_conditions(&self.criteria),
+            group_by: as_select(&self.group_by),
+            having: as_conditions(&self.having),
+            order_by: self
+                .order_by
+                .iter()
+                .map(|v| match v {
+                    OrderByOwned::Asc(column) => OrderBy::Asc(column),
+                    OrderByOwned::Desc(column) => OrderBy::Desc(column),
+                    OrderByOwned::Nulls(column, desc, nulls) => {
+                        OrderBy::Nulls(column, *desc, *nulls)
+                    }
+                    OrderByOwned::Raw(clause) => OrderBy::Raw(&clause.expr, clause.params()),
+                })
+                .collect(),
+            offset: self.offset,
+            limit: self.limit,
+            compounds: self
+                .compounds
+                .iter()
+                .map(|(op, query)| (*op, query.as_sub()))
+                .collect(),
+            distinct: self.distinct.as_deref().map(as_strs),
+            lock: self.lock,
+            lock_wait: self.lock_wait,
+        }
+    }
+
+    #[inline]
+    fn as_sub(&self) -> SubQuery<'_> {
+        SubQuery::Owned(Box::new(self.as_query()))
+    }
+}
+
+fn to_strings(columns: &[&str]) -> Vec<String> {
+    columns.iter().map(|v| (*v).to_owned()).collect()
+}
+
+fn as_strs(columns: &[String]) -> Cow<'_, [&str]> {
+    columns.iter().map(String::as_str).collect()
+}
+
+fn as_select(select: &SelectOwned) -> Select<'_> {
+    match select {
+        SelectOwned::Columns(columns) => Select::Columns(as_strs(columns)),
+        SelectOwned::Raw(clause) => Select::Raw(&clause.expr, clause.params()),
+        SelectOwned::None => Select::None,
+    }
+}
+
+fn as_conditions(conditions: &[ConditionOwned]) -> Vec<Condition<'_>> {
+    conditions
+        .iter()
+        .map(|v| match v {
+            ConditionOwned::And => Condition::And,
+            ConditionOwned::Or => Condition::Or,
+            ConditionOwned::Clause(clause) => Condition::Clause(&clause.expr, clause.params()),
+            ConditionOwned::In(column, query) => Condition::In(column, query.as_sub()),
+            ConditionOwned::Exists(query) => Condition::Exists(query.as_sub()),
+            ConditionOwned::Group(group) => Condition::Group(as_conditions(group)),
+        })
+        .collect()
+}
+
+fn add_condition(
+    conditions: &mut Vec<ConditionOwned>,
+    connector: ConditionOwned,
+    condition: ConditionOwned,
+) {
+    if !conditions.is_empty() {
+        conditions.push(connector);
+    }
+    conditions.push(condition);
+}
+
+fn add_group(
+    conditions: &mut Vec<ConditionOwned>,
+    connector: ConditionOwned,
+    group: Vec<ConditionOwned>,
+) {
+    if !group.is_empty() {
+        add_condition(conditions, connector, ConditionOwned::Group(group));
+    }
+}
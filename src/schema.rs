@@ -0,0 +1,384 @@
+//! Schema introspection and comparison between models and live tables.
+use crate::model::Model;
+use crate::{concat_ident, Database};
+use log::{debug, warn};
+use sqlx::{Executor, Row};
+
+/// Describes a column of a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    /// The column name.
+    pub name: String,
+
+    /// The column type as reported by the database, or the sqlx type name for model columns.
+    pub type_name: String,
+
+    /// Indicates if the column accepts NULL.
+    pub nullable: bool,
+
+    /// Indicates if the column is part of the primary key.
+    pub primary_key: bool,
+
+    /// Indicates if the column is auto incrementing.
+    pub increment: bool,
+}
+
+/// Differences between a model and its table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diff {
+    /// The table being compared.
+    pub table: String,
+
+    /// Columns defined by the model but absent from the table.
+    pub missing: Vec<Column>,
+
+    /// Columns present in the table but not defined by the model.
+    pub extra: Vec<Column>,
+
+    /// Columns whose type or nullability differs, as `(expected, actual)` pairs.
+    pub mistyped: Vec<(Column, Column)>,
+}
+
+impl Diff {
+    /// Returns `true` if the model matches the table.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.mistyped.is_empty()
+    }
+
+    /// Returns the `ALTER TABLE` statements that would reconcile the table with the model.
+    ///
+    /// SQLite cannot change the type of an existing column, so no statement is emitted for
+    /// mistyped columns on SQLite.
+    ///
+    /// Missing columns are added as nullable, with a warning for those that are not, because
+    /// existing rows have no value for them: the NOT NULL constraint has to be added once they
+    /// are filled.
+    pub fn alter_statements(&self) -> Vec<String> {
+        let mut statements = Vec::new();
+        for v in &self.missing {
+            if !v.nullable {
+                warn!(
+                    target: "sorm",
+                    "column {} of {} is added as nullable, set NOT NULL once it is filled",
+                    v.name,
+                    self.table
+                );
+            }
+            let mut sql = self.alter_table();
+            sql.push_str(" ADD COLUMN ");
+            concat_ident(&mut sql, &v.name);
+            sql.push(' ');
+            sql.push_str(&column_definition(&Column {
+                nullable: true,
+                ..v.clone()
+            }));
+            statements.push(sql);
+        }
+
+        for v in &self.extra {
+            let mut sql = self.alter_table();
+            sql.push_str(" DROP COLUMN ");
+            concat_ident(&mut sql, &v.name);
+            statements.push(sql);
+        }
+
+        #[cfg(feature = "postgres")]
+        for (expected, actual) in &self.mistyped {
+            let mut sql = self.alter_table();
+            if !compatible(&expected.type_name, &actual.type_name) {
+                sql.push_str(" ALTER COLUMN ");
+                concat_ident(&mut sql, &expected.name);
+                sql.push_str(" TYPE ");
+                sql.push_str(&expected.type_name);
+                sql.push(',');
+            }
+            sql.push_str(" ALTER COLUMN ");
+            concat_ident(&mut sql, &expected.name);
+            sql.push_str(match expected.nullable {
+                true => " DROP NOT NULL",
+                false => " SET NOT NULL",
+            });
+            statements.push(sql);
+        }
+
+        #[cfg(feature = "mysql")]
+        for (expected, _) in &self.mistyped {
+            let mut sql = self.alter_table();
+            sql.push_str(" MODIFY COLUMN ");
+            concat_ident(&mut sql, &expected.name);
+            sql.push(' ');
+            sql.push_str(&column_definition(expected));
+            statements.push(sql);
+        }
+
+        statements
+    }
+
+    fn alter_table(&self) -> String {
+        let mut sql = String::with_capacity(64);
+        sql.push_str("ALTER TABLE ");
+        concat_ident(&mut sql, &self.table);
+        sql
+    }
+}
+
+/// Compares the columns of a model against the live table.
+///
+/// Returns [`crate::Error::TableNotFound`] if the table does not exist.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorm::model::Model;
+/// # use sorm::{Database, Result};
+/// async fn check<M: Model>(db: impl sqlx::Executor<'_, Database = Database>) -> Result<()> {
+///     let diff = sorm::schema::diff::<M>(db).await?;
+///     for sql in diff.alter_statements() {
+///         println!("{};", sql);
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn diff<M: Model>(
+    executor: impl Executor<'_, Database = Database>,
+) -> crate::Result<Diff> {
+    let actual = columns(executor, M::TABLE).await?;
+    Ok(compare(M::TABLE, &M::describe(), &actual))
+}
+
+/// Compares the `expected` columns against the `actual` columns of `table`.
+pub fn compare(table: &str, expected: &[Column], actual: &[Column]) -> Diff {
+    let mut diff = Diff {
+        table: table.to_string(),
+        missing: Vec::new(),
+        extra: Vec::new(),
+        mistyped: Vec::new(),
+    };
+
+    for v in expected {
+        match actual.iter().find(|c| c.name == v.name) {
+            Some(c) => {
+                if v.nullable != c.nullable || !compatible(&v.type_name, &c.type_name) {
+                    diff.mistyped.push((v.clone(), c.clone()));
+                }
+            }
+            None => diff.missing.push(v.clone()),
+        }
+    }
+
+    for v in actual {
+        if !expected.iter().any(|c| c.name == v.name) {
+            diff.extra.push(v.clone());
+        }
+    }
+
+    diff
+}
+
+/// Returns the tables of the current database, sorted by name.
+#[cfg(feature = "sqlite")]
+pub async fn tables(
+    executor: impl Executor<'_, Database = Database>,
+) -> crate::Result<Vec<String>> {
+    let sql = "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' \
+        ORDER BY name";
+    debug!(target: "sorm", "{}", sql);
+    Ok(sqlx::query_scalar(sql).fetch_all(executor).await?)
+}
+
+/// Returns the tables of the current database, sorted by name.
+#[cfg(feature = "mysql")]
+pub async fn tables(
+    executor: impl Executor<'_, Database = Database>,
+) -> crate::Result<Vec<String>> {
+    let sql = "SELECT CAST(TABLE_NAME AS CHAR) FROM information_schema.TABLES \
+        WHERE TABLE_SCHEMA = DATABASE() AND TABLE_TYPE = 'BASE TABLE' ORDER BY TABLE_NAME";
+    debug!(target: "sorm", "{}", sql);
+    Ok(sqlx::query_scalar(sql).fetch_all(executor).await?)
+}
+
+/// Returns the tables of the current schema, sorted by name.
+#[cfg(feature = "postgres")]
+pub async fn tables(
+    executor: impl Executor<'_, Database = Database>,
+) -> crate::Result<Vec<String>> {
+    let sql = "SELECT table_name::text FROM information_schema.tables \
+        WHERE table_schema = current_schema() AND table_type = 'BASE TABLE' ORDER BY table_name";
+    debug!(target: "sorm", "{}", sql);
+    Ok(sqlx::query_scalar(sql).fetch_all(executor).await?)
+}
+
+/// Returns the columns of `table`, in table order.
+///
+/// Returns [`crate::Error::TableNotFound`] if the table does not exist.
+#[cfg(feature = "sqlite")]
+pub async fn columns(
+    executor: impl Executor<'_, Database = Database>,
+    table: &str,
+) -> crate::Result<Vec<Column>> {
+    let sql = r#"SELECT name, type, "notnull", pk FROM pragma_table_info(?)"#;
+    debug!(target: "sorm", "{}", sql);
+    let rows = sqlx::query(sql).bind(table).fetch_all(executor).await?;
+    if rows.is_empty() {
+        return Err(crate::Error::TableNotFound(table.to_string()));
+    }
+    let mut columns = Vec::with_capacity(rows.len());
+    let mut primary_keys = 0;
+    for row in rows {
+        let type_name: String = row.try_get(1)?;
+        let primary_key = row.try_get::<i64, _>(3)? > 0;
+        primary_keys += primary_key as usize;
+        columns.push(Column {
+            name: row.try_get(0)?,
+            // A single INTEGER PRIMARY KEY column is an alias for the rowid.
+            increment: primary_key && type_name.eq_ignore_ascii_case("INTEGER"),
+            type_name,
+            nullable: row.try_get::<i64, _>(2)? == 0 && !primary_key,
+            primary_key,
+        });
+    }
+    if primary_keys > 1 {
+        for v in &mut columns {
+            v.increment = false;
+        }
+    }
+    Ok(columns)
+}
+
+/// Returns the columns of `table`, in table order.
+///
+/// Returns [`crate::Error::TableNotFound`] if the table does not exist.
+#[cfg(feature = "mysql")]
+pub async fn columns(
+    executor: impl Executor<'_, Database = Database>,
+    table: &str,
+) -> crate::Result<Vec<Column>> {
+    let sql = "SELECT CAST(COLUMN_NAME AS CHAR), CAST(COLUMN_TYPE AS CHAR), \
+        IS_NULLABLE = 'YES', COLUMN_KEY = 'PRI', EXTRA LIKE '%auto_increment%' \
+        FROM information_schema.COLUMNS WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? \
+        ORDER BY ORDINAL_POSITION";
+    debug!(target: "sorm", "{}", sql);
+    let rows = sqlx::query(sql).bind(table).fetch_all(executor).await?;
+    if rows.is_empty() {
+        return Err(crate::Error::TableNotFound(table.to_string()));
+    }
+    let mut columns = Vec::with_capacity(rows.len());
+    for row in rows {
+        columns.push(Column {
+            name: row.try_get(0)?,
+            type_name: row.try_get(1)?,
+            nullable: row.try_get::<i64, _>(2)? != 0,
+            primary_key: row.try_get::<i64, _>(3)? != 0,
+            increment: row.try_get::<i64, _>(4)? != 0,
+        });
+    }
+    Ok(columns)
+}
+
+/// Returns the columns of `table`, in table order.
+///
+/// Returns [`crate::Error::TableNotFound`] if the table does not exist.
+#[cfg(feature = "postgres")]
+pub async fn columns(
+    executor: impl Executor<'_, Database = Database>,
+    table: &str,
+) -> crate::Result<Vec<Column>> {
+    let sql = "SELECT c.column_name::text, c.udt_name::text, c.is_nullable = 'YES', \
+        EXISTS (SELECT 1 FROM information_schema.table_constraints t \
+        JOIN information_schema.key_column_usage k ON k.constraint_schema = t.constraint_schema \
+        AND k.constraint_name = t.constraint_name \
+        WHERE t.constraint_type = 'PRIMARY KEY' AND t.table_schema = c.table_schema \
+        AND t.table_name = c.table_name AND k.column_name = c.column_name), \
+        COALESCE(c.column_default LIKE 'nextval(%', false) OR c.is_identity = 'YES' \
+        FROM information_schema.columns c WHERE c.table_schema = current_schema() \
+        AND c.table_name = $1 ORDER BY c.ordinal_position";
+    debug!(target: "sorm", "{}", sql);
+    let rows = sqlx::query(sql).bind(table).fetch_all(executor).await?;
+    if rows.is_empty() {
+        return Err(crate::Error::TableNotFound(table.to_string()));
+    }
+    let mut columns = Vec::with_capacity(rows.len());
+    for row in rows {
+        columns.push(Column {
+            name: row.try_get(0)?,
+            type_name: row.try_get(1)?,
+            nullable: row.try_get(2)?,
+            primary_key: row.try_get(3)?,
+            increment: row.try_get(4)?,
+        });
+    }
+    Ok(columns)
+}
+
+fn column_definition(column: &Column) -> String {
+    #[cfg(feature = "mysql")]
+    let mut s = match column.type_name.as_str() {
+        // MySQL requires a length for VARCHAR and VARBINARY.
+        "VARCHAR" => "VARCHAR(255)".to_string(),
+        "VARBINARY" => "VARBINARY(255)".to_string(),
+        v => v.to_string(),
+    };
+    #[cfg(not(feature = "mysql"))]
+    let mut s = column.type_name.clone();
+    if !column.nullable {
+        s.push_str(" NOT NULL");
+    }
+    s
+}
+
+/// Returns `true` if values of the `expected` type can be stored in a column of the `actual` type.
+fn compatible(expected: &str, actual: &str) -> bool {
+    normalize(expected) == normalize(actual)
+}
+
+#[cfg(feature = "sqlite")]
+fn normalize(type_name: &str) -> &'static str {
+    // https://www.sqlite.org/datatype3.html#determination_of_column_affinity
+    let type_name = type_name.to_ascii_uppercase();
+    if type_name.contains("INT") {
+        "INTEGER"
+    } else if type_name.contains("CHAR") || type_name.contains("CLOB") || type_name.contains("TEXT")
+    {
+        "TEXT"
+    } else if type_name.contains("BLOB") || type_name.is_empty() {
+        "BLOB"
+    } else if type_name.contains("REAL") || type_name.contains("FLOA") || type_name.contains("DOUB")
+    {
+        "REAL"
+    } else {
+        // Values with NUMERIC affinity are stored as integers whenever possible.
+        "INTEGER"
+    }
+}
+
+#[cfg(feature = "mysql")]
+fn normalize(type_name: &str) -> String {
+    let type_name = type_name.to_ascii_uppercase();
+    if type_name.starts_with("TINYINT(1)") {
+        return "BOOLEAN".to_string();
+    }
+    let unsigned = type_name.contains("UNSIGNED");
+    let base = type_name.split(['(', ' ']).next().unwrap_or_default();
+    let base = match base {
+        "INTEGER" => "INT",
+        "BOOL" => "BOOLEAN",
+        "CHAR" | "TEXT" | "TINYTEXT" | "MEDIUMTEXT" | "LONGTEXT" | "ENUM" => "VARCHAR",
+        "BINARY" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB" => "VARBINARY",
+        "BLOB" => "VARBINARY",
+        v => v,
+    };
+    match unsigned {
+        true => format!("{} UNSIGNED", base),
+        false => base.to_string(),
+    }
+}
+
+#[cfg(feature = "postgres")]
+fn normalize(type_name: &str) -> String {
+    let type_name = type_name.to_ascii_uppercase();
+    match type_name.as_str() {
+        "VARCHAR" | "BPCHAR" | "NAME" | "CITEXT" => "TEXT".to_string(),
+        _ => type_name,
+    }
+}
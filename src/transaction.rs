@@ -0,0 +1,197 @@
+//! Transactions with automatic retry.
+use std::time::Duration;
+
+use futures_core::future::BoxFuture;
+use log::debug;
+use sqlx::{Connection, Pool, Transaction};
+
+use crate::{Database, Error};
+
+/// The isolation level of a transaction.
+///
+/// SQLite transactions are always serializable, the level is ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Isolation {
+    /// READ UNCOMMITTED.
+    ReadUncommitted,
+    /// READ COMMITTED.
+    ReadCommitted,
+    /// REPEATABLE READ.
+    RepeatableRead,
+    /// SERIALIZABLE.
+    Serializable,
+}
+
+impl Isolation {
+    #[cfg(any(feature = "mysql", feature = "postgres"))]
+    fn as_sql(self) -> &'static str {
+        match self {
+            Isolation::ReadUncommitted => "READ UNCOMMITTED",
+            Isolation::ReadCommitted => "READ COMMITTED",
+            Isolation::RepeatableRead => "REPEATABLE READ",
+            Isolation::Serializable => "SERIALIZABLE",
+        }
+    }
+}
+
+/// The options of [`transaction_with`].
+#[derive(Debug, Clone, Default)]
+pub struct TransactionOptions {
+    isolation: Option<Isolation>,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl TransactionOptions {
+    /// Constructs options using the default isolation level of the database, without retry.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the isolation level of the transaction.
+    #[inline]
+    pub fn isolation(mut self, isolation: Isolation) -> Self {
+        self.isolation = Some(isolation);
+        self
+    }
+
+    /// Retries the transaction up to `retries` times on serialization failures and deadlocks,
+    /// waiting `backoff` before the first retry, and twice as long before each next one.
+    ///
+    /// The wait needs the `runtime-tokio` feature, the transaction is retried at once without it.
+    #[inline]
+    pub fn retry(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+}
+
+/// Runs `f` in a transaction, committing it if `f` returns `Ok`, and rolling it back if `f`
+/// returns `Err` or panics.
+///
+/// # Examples
+///
+/// ```rust
+/// # use sorm::model::Model;
+/// # use sorm::sorm;
+/// #[sorm(table = "users")]
+/// struct User {
+///     #[sorm(primary_key(increment))]
+///     id: i64,
+///     name: String,
+/// }
+///
+/// async fn rename(pool: &sqlx::Pool<sorm::Database>) -> sorm::Result<()> {
+///     sorm::transaction(pool, |tx| {
+///         Box::pin(async move {
+///             let mut user = User::find(&mut **tx, &1).await?;
+///             user.set_name("foo".to_owned());
+///             user.update(&mut **tx).await
+///         })
+///     })
+///     .await
+/// }
+/// ```
+#[inline]
+pub async fn transaction<T, F>(pool: &Pool<Database>, f: F) -> crate::Result<T>
+where
+    F: for<'t, 'c> FnMut(&'t mut Transaction<'c, Database>) -> BoxFuture<'t, crate::Result<T>>,
+{
+    transaction_with(pool, &TransactionOptions::new(), f).await
+}
+
+/// Runs `f` in a transaction with `options`, see [`transaction`].
+///
+/// On serialization failures and deadlocks, `f` is called again in a new transaction if
+/// [`TransactionOptions::retry`] allows it. These are the errors `40001` and `40P01` on
+/// PostgreSQL, `1213` on MySQL and `SQLITE_BUSY` on SQLite.
+pub async fn transaction_with<T, F>(
+    pool: &Pool<Database>,
+    options: &TransactionOptions,
+    mut f: F,
+) -> crate::Result<T>
+where
+    F: for<'t, 'c> FnMut(&'t mut Transaction<'c, Database>) -> BoxFuture<'t, crate::Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match run(pool, options.isolation, &mut f).await {
+            Err(err) if attempt < options.retries && is_retryable(&err) => {
+                debug!(target: "sorm", "retrying transaction: {}", err);
+                #[cfg(feature = "runtime-tokio")]
+                tokio::time::sleep(options.backoff.saturating_mul(1 << attempt.min(16))).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Runs `f` once in a transaction.
+async fn run<T, F>(
+    pool: &Pool<Database>,
+    isolation: Option<Isolation>,
+    f: &mut F,
+) -> crate::Result<T>
+where
+    F: for<'t, 'c> FnMut(&'t mut Transaction<'c, Database>) -> BoxFuture<'t, crate::Result<T>>,
+{
+    #[cfg(any(feature = "mysql", feature = "postgres"))]
+    use sqlx::Executor;
+
+    let mut conn = pool.acquire().await?;
+    // The level of the next transaction is set before it starts.
+    #[cfg(feature = "mysql")]
+    if let Some(isolation) = isolation {
+        let sql = format!("SET TRANSACTION ISOLATION LEVEL {}", isolation.as_sql());
+        debug!(target: "sorm", "{}", sql);
+        conn.execute(sql.as_str()).await?;
+    }
+    let mut tx = Connection::begin(&mut *conn).await?;
+    // The level is set by the first statement of the transaction.
+    #[cfg(feature = "postgres")]
+    if let Some(isolation) = isolation {
+        let sql = format!("SET TRANSACTION ISOLATION LEVEL {}", isolation.as_sql());
+        debug!(target: "sorm", "{}", sql);
+        tx.execute(sql.as_str()).await?;
+    }
+    #[cfg(feature = "sqlite")]
+    let _ = isolation;
+
+    match f(&mut tx).await {
+        Ok(value) => {
+            tx.commit().await?;
+            Ok(value)
+        }
+        Err(err) => {
+            // The error of `f` is returned, not the one of the rollback.
+            let _ = tx.rollback().await;
+            Err(err)
+        }
+    }
+}
+
+/// Returns `true` if the error is a serialization failure or a deadlock.
+fn is_retryable(err: &Error) -> bool {
+    let err = match err {
+        Error::Sqlx(sqlx::Error::Database(err)) => err,
+        _ => return false,
+    };
+
+    #[cfg(feature = "postgres")]
+    return matches!(err.code().as_deref(), Some("40001" | "40P01"));
+
+    #[cfg(feature = "mysql")]
+    return err
+        .try_downcast_ref::<sqlx::mysql::MySqlDatabaseError>()
+        .is_some_and(|v| v.number() == 1213);
+
+    // The code is the extended result code, whose low byte is the primary one.
+    #[cfg(feature = "sqlite")]
+    return err
+        .code()
+        .and_then(|v| v.parse::<i32>().ok())
+        .is_some_and(|v| v & 0xff == 5);
+}